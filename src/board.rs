@@ -0,0 +1,163 @@
+use super::sudoku::Sudoku;
+use super::samurai::{self, Samurai};
+use super::value::SudokuValue;
+use super::difficulty::Rating;
+use super::logic::{LogicalSolver, Step};
+use super::constraint::Constraint;
+use super::size::Size;
+use super::error::SudokuError;
+
+/// Where the lines and cells of a board are drawn. Boards with squares only get dividers between the squares,
+/// bordered boards get room for a border between every two cells, for regions that aren't squares.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Layout {
+    /// The size of the grids the board is made of.
+    pub size: Size,
+    /// Number of rows and columns of the whole board, 21 for the five grids of a Samurai.
+    pub side: usize,
+    pub bordered: bool,
+    /// Leaves room for the clues outside of the grid, left of the rows and above the columns.
+    pub clues: bool,
+}
+
+/// A board the game can be played on, a single [Sudoku] or the overlapping grids of a [Samurai].
+/// Cells are `(row, col)` indices of the whole board, which has [Layout::side] rows and columns.
+pub trait Board: Clone {
+    /// Returns where the board is drawn.
+    fn layout(&self) -> Layout;
+
+    /// Returns the SudokuValue at the specified indices, `None` for cells that aren't part of the board.
+    fn get(&self, r: usize, c: usize) -> Option<&SudokuValue>;
+
+    /// Sets the value at the specified indices, returns an error for cells that aren't part of the board.
+    fn set(&mut self, r: usize, c: usize, val: SudokuValue) -> Result<(), SudokuError>;
+
+    /// Returns how often the SudokuValue is on the board.
+    fn count(&self, val: SudokuValue) -> usize;
+
+    /// Returns the number of fields that have a value.
+    fn hints(&self) -> usize;
+
+    /// Returns `true` if the board is completely solved.
+    fn solved(&self) -> bool;
+
+    /// Returns `true` if the board has a unique solution and none of its hints can be removed.
+    fn is_minimal(&self) -> bool;
+
+    /// Returns every pair of cells that can't have the same value but do.
+    fn conflicts(&self) -> Vec<((usize, usize), (usize, usize))>;
+
+    /// Returns the steps of the [LogicalSolver] until the board is solved or the solver is stuck.
+    fn solve_logically(&self) -> Vec<Step>;
+
+    fn rating(&self) -> Rating;
+
+    /// Returns the rules of the variant, empty for classic boards.
+    fn constraints(&self) -> &[Constraint];
+
+    /// Returns the region the cell lies in, only compared to the regions of other cells.
+    fn region(&self, r: usize, c: usize) -> usize;
+}
+
+impl Board for Sudoku {
+    fn layout(&self) -> Layout {
+        self.layout()
+    }
+
+
+    fn get(&self, r: usize, c: usize) -> Option<&SudokuValue> {
+        self.get(r, c)
+    }
+
+    fn set(&mut self, r: usize, c: usize, val: SudokuValue) -> Result<(), SudokuError> {
+        self.set(r, c, val)
+    }
+
+    fn count(&self, val: SudokuValue) -> usize {
+        self.count(val)
+    }
+
+    fn hints(&self) -> usize {
+        self.hints()
+    }
+
+    fn solved(&self) -> bool {
+        self.solved()
+    }
+
+    fn is_minimal(&self) -> bool {
+        self.is_minimal()
+    }
+
+    fn conflicts(&self) -> Vec<((usize, usize), (usize, usize))> {
+        self.conflicts()
+    }
+
+    fn solve_logically(&self) -> Vec<Step> {
+        LogicalSolver::new(self).solve()
+    }
+
+    fn rating(&self) -> Rating {
+        Rating::new(self)
+    }
+
+    fn constraints(&self) -> &[Constraint] {
+        self.constraints()
+    }
+
+    fn region(&self, r: usize, c: usize) -> usize {
+        self.regions().region(r, c)
+    }
+}
+
+impl Board for Samurai {
+    fn layout(&self) -> Layout {
+        self.layout()
+    }
+
+
+    fn get(&self, r: usize, c: usize) -> Option<&SudokuValue> {
+        self.get(r, c)
+    }
+
+    fn set(&mut self, r: usize, c: usize, val: SudokuValue) -> Result<(), SudokuError> {
+        self.set(r, c, val)
+    }
+
+    fn count(&self, val: SudokuValue) -> usize {
+        self.count(val)
+    }
+
+    fn hints(&self) -> usize {
+        self.hints()
+    }
+
+    fn solved(&self) -> bool {
+        self.solved()
+    }
+
+    fn is_minimal(&self) -> bool {
+        self.is_minimal()
+    }
+
+    fn conflicts(&self) -> Vec<((usize, usize), (usize, usize))> {
+        self.conflicts()
+    }
+
+    fn solve_logically(&self) -> Vec<Step> {
+        self.solve_logically()
+    }
+
+    fn rating(&self) -> Rating {
+        self.rating()
+    }
+
+    fn constraints(&self) -> &[Constraint] {
+        &[]
+    }
+
+    fn region(&self, r: usize, c: usize) -> usize {
+        let size = self.layout().size;
+        (r / size.box_rows) * samurai::SIDE + c / size.box_cols
+    }
+}
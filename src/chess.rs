@@ -0,0 +1,103 @@
+use std::fmt::Formatter;
+use super::constraint::Constraint;
+use super::size::Size;
+use super::variant::Variant;
+
+/// A chess piece whose moves connect cells that can't have the same digit.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Piece {
+    Knight,
+    King,
+}
+
+impl Piece {
+    /// Returns `false` for boards that can't be filled with both pieces, like most small boards,
+    /// or whose random fills take too long, like the bigger boards and the variants with extra regions.
+    pub fn supports(size: Size, variant: Variant) -> bool {
+        (8..=9).contains(&size.side()) && matches!(variant, Variant::Classic | Variant::Killer | Variant::Jigsaw)
+    }
+
+    pub fn all() -> [Piece; 2] {
+        [Piece::Knight, Piece::King]
+    }
+
+    /// Returns a [Constraint::Chess] for every pair of cells a move of the piece apart.
+    /// Every pair is only added once, and king moves along a row or column are left out
+    /// because the row or column already forbids the repeated digit.
+    pub fn rules(self, size: Size) -> Vec<Constraint> {
+        let n = size.side() as isize;
+        // only the moves down the board, the moves up give the same pairs
+        let moves: &[(isize, isize)] = match self {
+            Piece::Knight => &[(1, -2), (1, 2), (2, -1), (2, 1)],
+            Piece::King   => &[(1, -1), (1, 1)]
+        };
+        let mut rules = Vec::new();
+        for r in 0..n {
+            for c in 0..n {
+                for &(dr, dc) in moves {
+                    let (r2, c2) = (r + dr, c + dc);
+                    if r2 < n && (0..n).contains(&c2) {
+                        rules.push(Constraint::Chess { piece: self, cells: [(r as usize, c as usize), (r2 as usize, c2 as usize)] });
+                    }
+                }
+            }
+        }
+        rules
+    }
+
+    /// Letter used in the [PuzzleId](super::puzzle_id::PuzzleId), `N` is already taken by [Symmetry::None](super::symmetry::Symmetry::None).
+    pub fn code(self) -> char {
+        match self {
+            Piece::Knight => 'J',
+            Piece::King   => 'K'
+        }
+    }
+
+    pub fn from_code(code: char) -> Option<Self> {
+        Piece::all().iter().copied().find(|piece| piece.code() == code)
+    }
+}
+
+impl std::fmt::Display for Piece {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Piece::Knight => "Anti-knight",
+            Piece::King   => "Anti-king"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::settings::Settings;
+    use crate::solver::Uniqueness;
+    use crate::sudoku::Sudoku;
+
+    #[test]
+    fn rules_connect_every_move_once() {
+        // a knight has 8 moves from the middle of the board, a king 8 of which 4 are in its row or column
+        let knight = Piece::Knight.rules(Size::NINE);
+        assert_eq!(knight.iter().filter(|rule| rule.contains(4, 4)).count(), 8);
+        let king = Piece::King.rules(Size::NINE);
+        assert_eq!(king.iter().filter(|rule| rule.contains(4, 4)).count(), 4);
+        assert!(king.contains(&Constraint::Chess { piece: Piece::King, cells: [(3, 3), (4, 4)] }));
+    }
+
+    #[test]
+    fn conflicts_flag_pieces() {
+        let settings = Settings { size: Size::NINE, difficulty: Difficulty::Easy, anti_knight: true, anti_king: true, ..Settings::default() };
+        let (puzzle, solution) = Sudoku::new(&settings, 3).unwrap();
+        assert_eq!(puzzle.uniqueness(), Uniqueness::Unique);
+        assert!(solution.solved() && solution.conflicts().is_empty());
+        // the pairs lie in different rows, columns and squares, only the pieces forbid the same digit
+        for (piece, a, b) in [(Piece::Knight, (2, 2), (3, 4)), (Piece::King, (2, 2), (3, 3))] {
+            let mut broken = solution.clone();
+            broken.set(b.0, b.1, *solution.get(a.0, a.1).unwrap()).unwrap();
+            assert!(broken.conflicts().contains(&(a, b)), "{}", piece);
+            assert!(!broken.solved(), "{}", piece);
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use std::io::{self, BufRead, Write};
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use sudoku_game::{Difficulty, Game, Sudoku, SudokuError};
+use sudoku_game::difficulty::Rating;
+use sudoku_game::format::{self, Format};
+use sudoku_game::puzzle_id::PuzzleId;
+use sudoku_game::settings::Settings;
+use sudoku_game::dlx::DancingLinks;
+use sudoku_game::solver::{Backtracking, Solver, Uniqueness};
+
+pub const USAGE: &str = r#"Usage: sudoku-game [COMMAND] [OPTIONS]
+
+Commands:
+  play [--difficulty D] [--seed S] [--puzzle FILE]
+                      Plays in the terminal, starting with the puzzle of the options if there are any (default)
+  solve [--solver S] [FILE...]
+                      Prints the solution of every puzzle
+  generate [--count N] [--difficulty D] [--seed S]
+                      Prints N puzzles, puzzle i of a seed S uses the seed S+i
+  validate [--solver S] [FILE...]
+                      Prints whether every puzzle has a unique solution or which cells break the rules
+  rate [FILE...]      Prints the difficulty, a score to sort by and the hardest technique every puzzle needs
+
+Options can come before or after the command, the first argument that isn't an option is the command.
+Puzzles are read from the files, in any of the formats of the game, or from the standard input
+with a puzzle of 81 cells per line. Every puzzle is answered on a line of its own, in order.
+Difficulties are easy, medium or hard. Solvers are backtracking (default) or dlx, the dancing links,
+to compare them with `time`."#;
+
+/// What the command line asks for, see [USAGE].
+pub enum Command {
+    Play { difficulty: Option<Difficulty>, seed: Option<u32>, puzzle: Option<PathBuf> },
+    Solve { files: Vec<PathBuf>, solver: &'static dyn Solver },
+    Generate { count: usize, difficulty: Difficulty, seed: Option<u32> },
+    Validate { files: Vec<PathBuf>, solver: &'static dyn Solver },
+    Rate { files: Vec<PathBuf> },
+    Help
+}
+
+impl Command {
+    /// Parses the arguments without the name of the program, no arguments play.
+    /// The first argument that isn't an option or the value of one is the command, the ones after it are files.
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+        let mut name: Option<String> = None;
+        let mut options = Options::default();
+        let mut files = Vec::new();
+        while let Some(arg) = args.next() {
+            let mut value = |option: &str| args.next().ok_or_else(|| format!("{} needs a value", option));
+            match arg.as_str() {
+                "--difficulty" => options.difficulty = Some(Difficulty::try_from(value(&arg)?.as_str()).map_err(|err| err.to_string())?),
+                "--seed" => options.seed = Some(number(&arg, &value(&arg)?)?),
+                "--count" => options.count = Some(number(&arg, &value(&arg)?)?),
+                "--puzzle" => options.puzzle = Some(PathBuf::from(value(&arg)?)),
+                "--solver" => options.solver = Some(solver(&value(&arg)?)?),
+                "-h" | "--help" => return Ok(Command::Help),
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+                _ if name.is_none() => name = Some(arg),
+                _ => files.push(PathBuf::from(arg))
+            }
+        }
+        let no_files = |files: &[PathBuf]| match files.first() {
+            Some(file) => Err(format!("Unexpected argument {}", file.display())),
+            None => Ok(())
+        };
+        match name.as_deref() {
+            None | Some("play") => {
+                if options.puzzle.is_some() && (options.difficulty.is_some() || options.seed.is_some()) {
+                    return Err("--puzzle can't be combined with --difficulty or --seed".to_string());
+                }
+                options.only(&["--difficulty", "--seed", "--puzzle"])?;
+                no_files(&files)?;
+                Ok(Command::Play { difficulty: options.difficulty, seed: options.seed, puzzle: options.puzzle })
+            },
+            Some("generate") => {
+                options.only(&["--count", "--difficulty", "--seed"])?;
+                no_files(&files)?;
+                Ok(Command::Generate { count: options.count.unwrap_or(1), difficulty: options.difficulty.unwrap_or(Difficulty::Medium), seed: options.seed })
+            },
+            Some(name @ ("solve" | "validate")) => {
+                options.only(&["--solver"])?;
+                let solver = options.solver.unwrap_or(&Backtracking);
+                Ok(match name {
+                    "solve" => Command::Solve { files, solver },
+                    _ => Command::Validate { files, solver }
+                })
+            },
+            Some("rate") => {
+                options.only(&[])?;
+                Ok(Command::Rate { files })
+            },
+            Some(name) => Err(format!("Unknown command {}", name))
+        }
+    }
+}
+
+/// The options given to any command, before checking that the command takes them.
+#[derive(Default)]
+struct Options {
+    difficulty: Option<Difficulty>,
+    seed: Option<u32>,
+    count: Option<usize>,
+    puzzle: Option<PathBuf>,
+    solver: Option<&'static dyn Solver>,
+}
+
+impl Options {
+    fn only(&self, allowed: &[&str]) -> Result<(), String> {
+        let given = [("--difficulty", self.difficulty.is_some()), ("--seed", self.seed.is_some()),
+                     ("--count", self.count.is_some()), ("--puzzle", self.puzzle.is_some()), ("--solver", self.solver.is_some())];
+        match given.iter().find(|&&(option, is_given)| is_given && !allowed.contains(&option)) {
+            Some((option, _)) => Err(format!("{} is not an option of this command", option)),
+            None => Ok(())
+        }
+    }
+}
+
+fn number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} needs a number, not {}", option, value))
+}
+
+fn solver(name: &str) -> Result<&'static dyn Solver, String> {
+    match name {
+        "backtracking" => Ok(&Backtracking),
+        "dlx" => Ok(&DancingLinks),
+        _ => Err(format!("Unknown solver {}, use backtracking or dlx", name))
+    }
+}
+
+/// Creates the game `play` starts with, `None` if it starts in the menu.
+pub fn first_game(difficulty: Option<Difficulty>, seed: Option<u32>, puzzle: Option<PathBuf>) -> Result<Option<Game<Sudoku>>, String> {
+    if let Some(path) = puzzle {
+        let text = std::fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let given = format::read(&text).map_err(|err| format!("{}: {}", path.display(), err))?
+            .into_iter().next()
+            .ok_or_else(|| format!("{}: The file has no puzzle", path.display()))?;
+        let solution = unique_solution(&given, &Backtracking).map_err(|err| format!("{}: {}", path.display(), err))?;
+        return Ok(Some(Game::new(None, given, solution)));
+    }
+    if difficulty.is_none() && seed.is_none() {
+        return Ok(None);
+    }
+    let settings = Settings { difficulty: difficulty.unwrap_or(Difficulty::Medium), ..Settings::default() };
+    let id = PuzzleId { settings, seed: seed.unwrap_or_else(rand::random) };
+    let (given, solution) = id.generate().map_err(|err| err.to_string())?;
+    Ok(Some(Game::new(Some(id), given, solution)))
+}
+
+fn unique_solution(sudoku: &Sudoku, solver: &dyn Solver) -> Result<Sudoku, SudokuError> {
+    match uniqueness(sudoku, solver) {
+        Uniqueness::Unique => Ok(solver.solve(sudoku).unwrap()),
+        Uniqueness::None => Err(SudokuError::Generation("The puzzle has no solution".to_string())),
+        Uniqueness::Multiple => Err(SudokuError::Generation("The puzzle has more than one solution".to_string()))
+    }
+}
+
+/// Runs any command but `play` and `help`, returns `false` if a puzzle couldn't be read or answered.
+pub fn run(command: Command) -> io::Result<bool> {
+    let result = answer(command, io::stdin().lock(), &mut io::stdout().lock());
+    match result {
+        // the reader of a pipeline like `| head` stopped reading
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(true),
+        result => result
+    }
+}
+
+/// Runs the command with the puzzles of the files or of `input`, see [run].
+fn answer<R: BufRead, W: Write>(command: Command, input: R, out: &mut W) -> io::Result<bool> {
+    match command {
+        Command::Solve { files, solver } => each_puzzle(&files, input, out, |sudoku| {
+            unique_solution(sudoku, solver).map(|solution| Format::Line.write(&solution))
+        }),
+        Command::Validate { files, solver } => {
+            let mut valid = true;
+            let read = each_puzzle(&files, input, out, |sudoku| {
+                let (line, unique) = validate(sudoku, solver);
+                valid &= unique;
+                Ok(line)
+            });
+            read.map(|ok| ok && valid)
+        },
+        Command::Rate { files } => each_puzzle(&files, input, out, |sudoku| {
+            let rating = Rating::new(sudoku);
+            let difficulty = rating.difficulty().map_or("Unrated".to_string(), |difficulty| difficulty.to_string());
+            let score = rating.score().map_or("-".to_string(), |score| score.to_string());
+            Ok(format!("{}\t{}\t{}\t{}\n", Format::Line.write(sudoku).trim_end(), difficulty, score, rating))
+        }),
+        Command::Generate { count, difficulty, seed } => generate(out, count, difficulty, seed),
+        Command::Play { .. } | Command::Help => Ok(true)
+    }
+}
+
+/// Returns the puzzle followed by `unique`, `no solution`, `multiple solutions` or the cells with the same value that can't have it,
+/// and whether it's unique.
+fn validate(sudoku: &Sudoku, solver: &dyn Solver) -> (String, bool) {
+    let conflicts = sudoku.conflicts();
+    let (status, unique) = if !conflicts.is_empty() {
+        let cells = conflicts.iter()
+            .map(|&((r1, c1), (r2, c2))| format!("r{}c{} r{}c{}", r1 + 1, c1 + 1, r2 + 1, c2 + 1))
+            .collect::<Vec<_>>();
+        (format!("conflicts {}", cells.join(", ")), false)
+    } else {
+        match uniqueness(sudoku, solver) {
+            Uniqueness::Unique => ("unique".to_string(), true),
+            Uniqueness::None => ("no solution".to_string(), false),
+            Uniqueness::Multiple => ("multiple solutions".to_string(), false)
+        }
+    };
+    (format!("{}\t{}\n", Format::Line.write(sudoku).trim_end(), status), unique)
+}
+
+fn uniqueness(sudoku: &Sudoku, solver: &dyn Solver) -> Uniqueness {
+    match solver.count(sudoku, 2) {
+        0 => Uniqueness::None,
+        1 => Uniqueness::Unique,
+        _ => Uniqueness::Multiple
+    }
+}
+
+fn generate<W: Write>(out: &mut W, count: usize, difficulty: Difficulty, seed: Option<u32>) -> io::Result<bool> {
+    let settings = Settings { difficulty, ..Settings::default() };
+    for i in 0..count {
+        let seed = seed.map_or_else(rand::random, |seed| seed.wrapping_add(i as u32));
+        match (PuzzleId { settings, seed }).generate() {
+            Ok((given, _)) => out.write_all(Format::Line.write(&given).as_bytes())?,
+            Err(err) => {
+                eprintln!("{}", err);
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Answers every puzzle of the files, or of `input` if there are none, with the line `answer` returns.
+/// The input is answered a line at a time, as soon as it's read.
+/// Errors are written to the standard error with the line or the file they are in, and the other puzzles are still answered.
+fn each_puzzle<R, W, F>(files: &[PathBuf], input: R, out: &mut W, mut answer: F) -> io::Result<bool>
+    where R: BufRead, W: Write, F: FnMut(&Sudoku) -> Result<String, SudokuError> {
+    let mut ok = true;
+    if files.is_empty() {
+        for (number, line) in input.lines().enumerate() {
+            // a collection of a single line, so empty lines and comments are skipped
+            match format::read_collection(&line?) {
+                Ok(sudokus) => for sudoku in sudokus {
+                    ok &= report(out, &format!("Line {}: ", number + 1), answer(&sudoku))?;
+                },
+                // the line of the error is counted from the start of the input
+                Err(SudokuError::Syntax { column, reason, .. }) => ok &= report(out, "", Err(SudokuError::Syntax { line: number + 1, column, reason }))?,
+                Err(err) => ok &= report(out, "", Err(err))?
+            }
+        }
+    }
+    for path in files {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("{}: {}", path.display(), err);
+                ok = false;
+                continue;
+            }
+        };
+        match format::read(&text) {
+            Ok(sudokus) => for (n, sudoku) in sudokus.iter().enumerate() {
+                ok &= report(out, &format!("{}: Puzzle {}: ", path.display(), n + 1), answer(sudoku))?;
+            },
+            Err(err) => ok &= report(out, &format!("{}: ", path.display()), Err(err))?
+        }
+    }
+    Ok(ok)
+}
+
+/// Writes the answer, or the error after where it happened, returns `false` for an error.
+fn report<W: Write>(out: &mut W, at: &str, answer: Result<String, SudokuError>) -> io::Result<bool> {
+    match answer {
+        Ok(line) => out.write_all(line.as_bytes()).map(|_| true),
+        Err(err) => {
+            eprintln!("{}{}", at, err);
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    const SOLUTION: &str = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    fn parse(args: &str) -> Result<Command, String> {
+        Command::parse(args.split_whitespace().map(String::from))
+    }
+
+    /// Returns what the command writes for the input and whether every puzzle was answered.
+    fn output(command: &str, input: &str) -> (String, bool) {
+        let mut out = Vec::new();
+        let ok = answer(parse(command).unwrap(), input.as_bytes(), &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), ok)
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert!(matches!(parse(""), Ok(Command::Play { difficulty: None, seed: None, puzzle: None })));
+        assert!(matches!(parse("play --difficulty h --seed 42"), Ok(Command::Play { difficulty: Some(Difficulty::Hard), seed: Some(42), puzzle: None })));
+        assert!(matches!(parse("generate --count 3"), Ok(Command::Generate { count: 3, difficulty: Difficulty::Medium, seed: None })));
+        assert!(matches!(parse("solve --solver dlx a.txt b.txt"), Ok(Command::Solve { files, .. }) if files.len() == 2));
+        assert!(matches!(parse("rate --help"), Ok(Command::Help)));
+        // options can come before the command as well
+        assert!(matches!(parse("--solver dlx solve a.txt"), Ok(Command::Solve { files, .. }) if files == [PathBuf::from("a.txt")]));
+        assert!(matches!(parse("--count 2 generate --difficulty e"), Ok(Command::Generate { count: 2, difficulty: Difficulty::Easy, seed: None })));
+        assert!(matches!(parse("--seed 7"), Ok(Command::Play { difficulty: None, seed: Some(7), puzzle: None })));
+        assert!(matches!(parse("--help"), Ok(Command::Help)));
+    }
+
+    #[test]
+    fn rejects_wrong_arguments() {
+        assert_eq!(parse("solve --hints 3").err().unwrap(), "Unknown option --hints");
+        assert_eq!(parse("rate --seed 1").err().unwrap(), "--seed is not an option of this command");
+        assert_eq!(parse("generate --solver dlx").err().unwrap(), "--solver is not an option of this command");
+        assert_eq!(parse("play --puzzle grid.sdk --seed 1").err().unwrap(), "--puzzle can't be combined with --difficulty or --seed");
+        assert_eq!(parse("generate --count").err().unwrap(), "--count needs a value");
+        assert_eq!(parse("generate --count many").err().unwrap(), "--count needs a number, not many");
+        assert_eq!(parse("validate --solver guess").err().unwrap(), "Unknown solver guess, use backtracking or dlx");
+        assert_eq!(parse("play grid.sdk").err().unwrap(), "Unexpected argument grid.sdk");
+        assert_eq!(parse("check").err().unwrap(), "Unknown command check");
+        assert_eq!(parse("--solver dlx generate").err().unwrap(), "--solver is not an option of this command");
+        assert_eq!(parse("--seed 1 rate").err().unwrap(), "--seed is not an option of this command");
+    }
+
+    #[test]
+    fn answers_every_line() {
+        let input = format!("{}\n\n{}\n", PUZZLE, PUZZLE);
+        for solver in ["backtracking", "dlx"] {
+            let (out, ok) = output(&format!("solve --solver {}", solver), &input);
+            assert_eq!(out, format!("{}\n{}\n", SOLUTION, SOLUTION));
+            assert!(ok);
+        }
+        // the unreadable line is reported and the other puzzles are still answered
+        let (out, ok) = output("solve", &format!("{}\nnot a puzzle\n{}\n", PUZZLE, PUZZLE));
+        assert_eq!(out, format!("{}\n{}\n", SOLUTION, SOLUTION));
+        assert!(!ok);
+    }
+
+    #[test]
+    fn validates_puzzles() {
+        let multiple = format!("1{}", ".".repeat(80));
+        // the last cell of the first row has no digit left, but no rule is broken yet
+        let none = format!("12345678.........9{}", ".".repeat(63));
+        let conflicts = format!("53..7...3{}", &PUZZLE[9..]);
+        let (out, ok) = output("validate", &[PUZZLE, &multiple, &none, &conflicts].join("\n"));
+        assert_eq!(out, format!("{}\tunique\n{}\tmultiple solutions\n{}\tno solution\n{}\tconflicts r1c2 r1c9, r1c9 r4c9\n", PUZZLE, multiple, none, conflicts));
+        assert!(!ok);
+        let (out, ok) = output("validate --solver dlx", PUZZLE);
+        assert_eq!(out, format!("{}\tunique\n", PUZZLE));
+        assert!(ok);
+    }
+
+    #[test]
+    fn rates_puzzles() {
+        // Arto Inkala's puzzle needs more than the known techniques
+        let beyond = "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        let (out, ok) = output("rate", &format!("{}\n{}", PUZZLE, beyond));
+        assert_eq!(out, format!("{}\tEasy\t51\tNaked Single x51\n{}\tUnrated\t-\tBeyond XY-Wing\n", PUZZLE, beyond));
+        assert!(ok);
+    }
+}
@@ -0,0 +1,267 @@
+use std::fmt::Formatter;
+use super::chess::Piece;
+
+/// A rule of a variant that comes on top of the rows, columns and squares.
+/// Cells are `(row, col)` indices.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Constraint {
+    /// Killer cage: the digits of the cells are all different and add up to the sum.
+    Cage { cells: Vec<(usize, usize)>, sum: u32 },
+    /// An extra region that has to contain every digit like a row, the shape is only used to draw and name it.
+    Region { shape: Shape, cells: Vec<(usize, usize)> },
+    /// Two cells a move of the chess piece apart, they can't have the same digit.
+    Chess { piece: Piece, cells: [(usize, usize); 2] },
+    /// Thermometer: the digits strictly increase from the bulb, the first cell, to the end.
+    Thermo { cells: Vec<(usize, usize)> },
+    /// The digit of the circle, the first cell, is the sum of the digits along the arrow, which can repeat.
+    Arrow { cells: Vec<(usize, usize)> },
+    /// Kropki dot between two neighbours, see [Dot].
+    Kropki { dot: Dot, cells: [(usize, usize); 2] },
+    /// Clue outside of a row or column, in order: the digits between the 1 and the biggest digit add up to the sum.
+    Sandwich { cells: Vec<(usize, usize)>, sum: u32 },
+    /// Greater-than sign between two neighbours: the digit of the first cell is smaller than the one of the second.
+    Inequality { cells: [(usize, usize); 2] },
+    /// Two orthogonal neighbours: with a bar between them their digits are consecutive, without one they aren't.
+    Consecutive { cells: [(usize, usize); 2], bar: bool },
+    /// A shaded cell whose digit is even or odd, see [Parity].
+    Parity { parity: Parity, cell: (usize, usize) },
+}
+
+/// The kinds of [Constraint::Kropki] dots.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dot {
+    /// The digits are consecutive.
+    White,
+    /// One digit is twice the other.
+    Black,
+}
+
+/// The kinds of [Constraint::Parity] cells.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+/// The kinds of extra [Constraint::Region]s.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shape {
+    /// One of the two main diagonals.
+    Diagonal,
+    /// A square window between the squares of a Windoku.
+    Window,
+}
+
+impl Constraint {
+    /// Returns every cell the constraint looks at.
+    pub fn cells(&self) -> &[(usize, usize)] {
+        match self {
+            Constraint::Cage { cells, .. } => cells,
+            Constraint::Region { cells, .. } => cells,
+            Constraint::Chess { cells, .. } => cells,
+            Constraint::Thermo { cells } => cells,
+            Constraint::Arrow { cells } => cells,
+            Constraint::Kropki { cells, .. } => cells,
+            Constraint::Sandwich { cells, .. } => cells,
+            Constraint::Inequality { cells } => cells,
+            Constraint::Consecutive { cells, .. } => cells,
+            Constraint::Parity { cell, .. } => std::slice::from_ref(cell)
+        }
+    }
+
+    /// Returns the cells that must not repeat a digit, like the cells of a cage.
+    pub fn unique_cells(&self) -> Option<&[(usize, usize)]> {
+        match self {
+            Constraint::Cage { cells, .. } => Some(cells),
+            Constraint::Region { cells, .. } => Some(cells),
+            Constraint::Chess { cells, .. } => Some(cells),
+            Constraint::Thermo { cells } => Some(cells),
+            Constraint::Arrow { .. } => None,
+            Constraint::Kropki { cells, .. } => Some(cells),
+            // the row or column is a unit anyway
+            Constraint::Sandwich { .. } | Constraint::Inequality { .. } | Constraint::Consecutive { .. } => None,
+            Constraint::Parity { .. } => None
+        }
+    }
+
+    /// Returns `false` if the values can't be completed to satisfy the constraint.
+    /// `value` returns the digit of a cell of [Constraint::cells], `0` for an empty cell.
+    /// Repeated digits in [Constraint::unique_cells] are not checked here.
+    pub fn allows<F: Fn(usize, usize) -> u8>(&self, side: usize, value: F) -> bool {
+        match self {
+            Constraint::Cage { cells, sum } => {
+                let (mut total, mut empty) = (0, 0);
+                for &(r, c) in cells {
+                    match value(r, c) {
+                        0 => empty += 1,
+                        digit => total += digit as u32
+                    }
+                }
+                // the smallest and largest sums the empty cells can still add
+                let side = side as u32;
+                let min = empty * (empty + 1) / 2;
+                let max = empty * side - empty * empty.saturating_sub(1) / 2;
+                total + min <= *sum && total + max >= *sum
+            },
+            Constraint::Region { .. } | Constraint::Chess { .. } => true,
+            Constraint::Thermo { cells } => {
+                // every digit leaves room for the smaller digits before it and the bigger ones after it
+                let (side, len) = (side as i32, cells.len() as i32);
+                let mut last: Option<(i32, i32)> = None;
+                for (i, &(r, c)) in cells.iter().enumerate() {
+                    let (i, digit) = (i as i32, value(r, c) as i32);
+                    if digit == 0 {
+                        continue;
+                    }
+                    if digit < i + 1 || digit > side - (len - 1 - i) {
+                        return false;
+                    }
+                    if let Some((j, before)) = last {
+                        if digit - before < i - j {
+                            return false;
+                        }
+                    }
+                    last = Some((i, digit));
+                }
+                true
+            },
+            Constraint::Arrow { cells } => {
+                let (mut total, mut empty) = (0, 0);
+                for &(r, c) in &cells[1..] {
+                    match value(r, c) {
+                        0 => empty += 1,
+                        digit => total += digit as u32
+                    }
+                }
+                // every empty cell of the arrow adds between 1 and side
+                let (min, max) = (total + empty, total + empty * side as u32);
+                match value(cells[0].0, cells[0].1) as u32 {
+                    0 => min <= side as u32,
+                    circle => min <= circle && max >= circle
+                }
+            },
+            Constraint::Kropki { dot, cells } => {
+                let fits = |a: u8, b: u8| match dot {
+                    Dot::White => a + 1 == b || b + 1 == a,
+                    Dot::Black => a == 2 * b || b == 2 * a
+                };
+                match (value(cells[0].0, cells[0].1), value(cells[1].0, cells[1].1)) {
+                    (0, 0) => true,
+                    (0, digit) | (digit, 0) => (1..=side as u8).any(|other| fits(digit, other)),
+                    (a, b) => fits(a, b)
+                }
+            },
+            Constraint::Sandwich { cells, sum } => {
+                let digits: Vec<u8> = cells.iter().map(|&(r, c)| value(r, c)).collect();
+                // the 1 and the biggest digit are where they are placed, or in any of the empty cells
+                let ends = |end: u8| -> Vec<usize> {
+                    match digits.iter().position(|&digit| digit == end) {
+                        Some(i) => vec![i],
+                        None => (0..digits.len()).filter(|&i| digits[i] == 0).collect()
+                    }
+                };
+                let (ones, biggest) = (ends(1), ends(side as u8));
+                // the empty cells between them take different digits from 2 to side - 1 that aren't placed yet
+                let free: Vec<u32> = (2..side as u8).filter(|digit| !digits.contains(digit)).map(u32::from).collect();
+                ones.iter().any(|&i| biggest.iter().any(|&j| {
+                    if i == j {
+                        return false;
+                    }
+                    let between = &digits[i.min(j) + 1..i.max(j)];
+                    let total: u32 = between.iter().map(|&digit| digit as u32).sum();
+                    let empty = between.iter().filter(|&&digit| digit == 0).count();
+                    empty <= free.len()
+                        && total + free[..empty].iter().sum::<u32>() <= *sum
+                        && total + free[free.len() - empty..].iter().sum::<u32>() >= *sum
+                }))
+            },
+            Constraint::Inequality { cells } => {
+                match (value(cells[0].0, cells[0].1), value(cells[1].0, cells[1].1)) {
+                    (0, 0) => true,
+                    (smaller, 0) => (smaller as usize) < side,
+                    (0, bigger) => bigger > 1,
+                    (smaller, bigger) => smaller < bigger
+                }
+            },
+            Constraint::Consecutive { cells, bar } => {
+                // a single digit always has a neighbouring digit and one that isn't
+                match (value(cells[0].0, cells[0].1), value(cells[1].0, cells[1].1)) {
+                    (0, _) | (_, 0) => true,
+                    (a, b) => (a.abs_diff(b) == 1) == *bar
+                }
+            },
+            Constraint::Parity { parity, cell } => {
+                match (parity, value(cell.0, cell.1)) {
+                    (_, 0) => true,
+                    (Parity::Even, digit) => digit.is_multiple_of(2),
+                    (Parity::Odd, digit) => !digit.is_multiple_of(2)
+                }
+            }
+        }
+    }
+
+    /// Returns `true` for constraints that are drawn between the cells, so the board needs room between every two cells.
+    pub fn is_drawn_between_cells(&self) -> bool {
+        matches!(self, Constraint::Thermo { .. } | Constraint::Arrow { .. } | Constraint::Kropki { .. } | Constraint::Inequality { .. }
+            | Constraint::Consecutive { bar: true, .. })
+    }
+
+    /// Returns `true` for constraints that are drawn as clues outside of the grid, next to their row or column.
+    pub fn is_drawn_outside_grid(&self) -> bool {
+        matches!(self, Constraint::Sandwich { .. })
+    }
+
+    /// Returns `true` if the constraint contains the cell.
+    pub fn contains(&self, r: usize, c: usize) -> bool {
+        self.cells().contains(&(r, c))
+    }
+}
+
+impl std::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constraint::Cage { cells, sum } => write!(f, "Cage {} ({} cell{})", sum, cells.len(), if cells.len() == 1 { "" } else { "s" }),
+            Constraint::Region { shape, .. } => write!(f, "{}", shape),
+            Constraint::Chess { piece, .. } => write!(f, "{}", piece),
+            Constraint::Thermo { cells } => write!(f, "Thermometer ({} cells)", cells.len()),
+            Constraint::Arrow { cells } => write!(f, "Arrow ({} cells)", cells.len() - 1),
+            Constraint::Kropki { dot, .. } => write!(f, "{} dot", dot),
+            Constraint::Sandwich { cells, sum } => write!(f, "Sandwich {} ({})", sum, if cells[0].0 == cells[1].0 { "row" } else { "column" }),
+            Constraint::Inequality { .. } => write!(f, "Greater-than sign"),
+            Constraint::Consecutive { bar: true, .. } => write!(f, "Consecutive"),
+            Constraint::Consecutive { bar: false, .. } => write!(f, "Non-consecutive"),
+            Constraint::Parity { parity, .. } => write!(f, "{} cell", parity)
+        }
+    }
+}
+
+impl std::fmt::Display for Shape {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Shape::Diagonal => "Diagonal",
+            Shape::Window   => "Window"
+        })
+    }
+}
+
+impl std::fmt::Display for Parity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Parity::Even => "Even",
+            Parity::Odd  => "Odd"
+        })
+    }
+}
+
+impl std::fmt::Display for Dot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Dot::White => "White",
+            Dot::Black => "Black"
+        })
+    }
+}
@@ -1,13 +1,37 @@
+use std::convert::TryFrom;
 use std::fmt::Formatter;
+use super::error::SudokuError;
+use super::logic::{LogicalSolver, Step, Technique};
+use super::sudoku::Sudoku;
+use super::size::Size;
 
-#[derive(Copy, Clone, Debug)]
+/// The value of a difficulty is the number of hints a generated 9x9 puzzle has.
+/// Medium has fewer hints than the 40 it had before puzzles were rated, most puzzles with 40 hints
+/// are solved with singles and are rated easy, so medium puzzles were rarely generated.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Difficulty {
     Hard = 30,
-    Medium = 40,
+    Medium = 32,
     Easy = 50,
 }
 
 impl Difficulty {
+    /// Returns the number of hints of a puzzle with this difficulty.
+    /// Up to 12x12 the hints are scaled to the number of fields of the board, bigger boards need
+    /// more of them to stay solvable with the known techniques and to keep generation fast.
+    pub fn hints(self, size: Size) -> usize {
+        match (size.side(), self) {
+            (16, Difficulty::Easy)   => 140,
+            (16, Difficulty::Medium) => 120,
+            (16, Difficulty::Hard)   => 112,
+            (25, Difficulty::Easy)   => 345,
+            (25, Difficulty::Medium) => 330,
+            (25, Difficulty::Hard)   => 322,
+            _ => self as usize * size.cells() / Size::NINE.cells()
+        }
+    }
+
     pub fn up(self) -> Self {
         match self {
             Difficulty::Hard   => Difficulty::Hard,
@@ -34,3 +58,97 @@ impl std::fmt::Display for Difficulty {
         })
     }
 }
+
+impl TryFrom<&str> for Difficulty {
+    type Error = SudokuError;
+
+    /// Parses the name of a difficulty or its first letter, like `medium` or `M`, case insensitive.
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard].into_iter()
+            .find(|difficulty| {
+                let full = difficulty.to_string();
+                name.eq_ignore_ascii_case(&full) || name.eq_ignore_ascii_case(&full[..1])
+            })
+            .ok_or_else(|| SudokuError::Parse(format!("Unknown difficulty {}, it has to be easy, medium or hard", name)))
+    }
+}
+
+/// Returns how many bands the rated difficulty is away from the wanted one,
+/// puzzles that can't be solved with logic count as harder than hard.
+pub fn distance(rated: Option<Difficulty>, wanted: Difficulty) -> usize {
+    (rank(rated) - rank(Some(wanted))).unsigned_abs() as usize
+}
+
+/// Returns `true` if the rated difficulty is harder than the wanted one, or if the puzzle can't be solved with logic.
+pub fn is_harder(rated: Option<Difficulty>, wanted: Difficulty) -> bool {
+    rank(rated) > rank(Some(wanted))
+}
+
+fn rank(difficulty: Option<Difficulty>) -> i32 {
+    match difficulty {
+        Some(Difficulty::Easy) => 0,
+        Some(Difficulty::Medium) => 1,
+        Some(Difficulty::Hard) => 2,
+        None => 3
+    }
+}
+
+/// Rates a puzzle by the hardest [Technique] the [LogicalSolver] needs to solve it
+/// and by how often that technique is used.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Rating {
+    /// `None` if the puzzle can't be solved with the known techniques.
+    pub hardest: Option<Technique>,
+    pub count: usize,
+}
+
+impl Rating {
+    pub fn new(sudoku: &Sudoku) -> Self {
+        let mut solver = LogicalSolver::new(sudoku);
+        let steps = solver.solve();
+        Rating::from_steps(&steps, solver.solved())
+    }
+
+    /// Rates the steps that solved a puzzle, `solved` is `false` if the steps got stuck.
+    pub fn from_steps(steps: &[Step], solved: bool) -> Self {
+        if !solved {
+            return Rating { hardest: None, count: 0 };
+        }
+        let hardest = steps.iter().map(|step| step.technique).max().unwrap_or(Technique::NakedSingle);
+        let count = steps.iter().filter(|step| step.technique == hardest).count();
+        Rating { hardest: Some(hardest), count }
+    }
+
+    /// Returns a number to sort puzzles by, the position of the hardest technique from 0 for a
+    /// [Technique::NakedSingle] times 100, plus how often it's used up to 99.
+    /// `None` if the puzzle can't be solved with logic.
+    pub fn score(&self) -> Option<usize> {
+        self.hardest.map(|hardest| hardest as usize * 100 + self.count.min(99))
+    }
+
+    /// Returns the band the rating falls into, `None` if the puzzle can't be solved with logic.
+    /// ```text
+    /// Easy   : singles and the techniques of variant constraints only
+    /// Medium : intersections, pairs, or up to two triples and quads
+    /// Hard   : more triples and quads, or any X-Wing, Swordfish or XY-Wing
+    /// ```
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        let hardest = self.hardest?;
+        Some(if hardest < Technique::PointingPair {
+            Difficulty::Easy
+        } else if hardest <= Technique::HiddenPair || (hardest <= Technique::HiddenQuad && self.count <= 2) {
+            Difficulty::Medium
+        } else {
+            Difficulty::Hard
+        })
+    }
+}
+
+impl std::fmt::Display for Rating {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.hardest {
+            Some(technique) => write!(f, "{} x{}", technique, self.count),
+            None => write!(f, "Beyond {}", Technique::XYWing)
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use std::convert::TryFrom;
+use rand::RngCore;
+use rand::seq::SliceRandom;
+use super::solver::Solver;
+use super::sudoku::Sudoku;
+use super::value::SudokuValue;
+use super::error::SudokuError;
+
+/// Sparse 0/1 matrix for Knuth's Algorithm X, stored as circular doubly linked lists.
+/// Node `0` is the root, the next nodes are the column headers, the rest are the 1s of the matrix.
+/// Every row covers some columns, a solution is a set of rows that covers every primary column exactly once
+/// and every secondary column at most once.
+pub struct Matrix {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    col: Vec<usize>,
+    row: Vec<usize>,
+    // number of nodes in a column, indexed by the column header
+    size: Vec<usize>,
+    // first node of each row
+    rows: Vec<usize>,
+}
+
+impl Matrix {
+    /// Creates a matrix with `primary` and then `secondary` columns and no rows.
+    pub fn new(primary: usize, secondary: usize) -> Self {
+        let columns = primary + secondary;
+        let n = columns + 1;
+        // secondary headers link only to themselves, so the search never has to cover them
+        let mut matrix = Matrix {
+            left: (0..n).map(|i| match i {
+                0 => primary,
+                _ if i > primary => i,
+                _ => i - 1
+            }).collect(),
+            right: (0..n).map(|i| match i {
+                _ if i > primary => i,
+                _ if i == primary => 0,
+                _ => i + 1
+            }).collect(),
+            up: (0..n).collect(),
+            down: (0..n).collect(),
+            col: (0..n).collect(),
+            row: vec![usize::MAX; n],
+            size: vec![0; n],
+            rows: Vec::new(),
+        };
+        matrix.size[0] = usize::MAX;
+        matrix
+    }
+
+    /// Adds a row that covers the given columns and returns its index.
+    pub fn add_row(&mut self, columns: &[usize]) -> usize {
+        let id = self.rows.len();
+        let first = self.left.len();
+        for (n, &c) in columns.iter().enumerate() {
+            let header = c + 1;
+            let node = first + n;
+            self.left.push(if n == 0 { first + columns.len() - 1 } else { node - 1 });
+            self.right.push(if n == columns.len() - 1 { first } else { node + 1 });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.col.push(header);
+            self.row.push(id);
+            self.size[header] += 1;
+        }
+        self.rows.push(first);
+        id
+    }
+
+    /// Removes the columns of a row and every row that collides with it, as if the row was chosen.
+    /// Returns `false` if the row was already removed by an earlier selection.
+    pub fn select(&mut self, row: usize) -> bool {
+        let first = self.rows[row];
+        let mut node = first;
+        loop {
+            // covering a column unlinks the other nodes of its rows
+            if self.down[self.up[node]] != node {
+                return false;
+            }
+            node = self.right[node];
+            if node == first {
+                break;
+            }
+        }
+        loop {
+            self.cover(self.col[node]);
+            node = self.right[node];
+            if node == first {
+                return true;
+            }
+        }
+    }
+
+    /// Searches exact covers of the remaining columns until `limit` are found and returns their number.
+    /// `accept` is called with the rows chosen so far after every chosen row,
+    /// the search backtracks if it returns `false`.
+    /// The first cover found is written to `solution` as row indices.
+    /// The rows of a column are tried in random order if a rng is given.
+    pub fn search(&mut self, limit: usize, solution: &mut Vec<usize>, rng: Option<&mut dyn RngCore>, accept: &dyn Fn(&[usize]) -> bool) -> usize {
+        let mut found = 0;
+        let mut partial = Vec::new();
+        self.search_from(limit, &mut found, &mut partial, solution, rng, accept);
+        found
+    }
+
+    fn search_from(&mut self, limit: usize, found: &mut usize, partial: &mut Vec<usize>, solution: &mut Vec<usize>, mut rng: Option<&mut dyn RngCore>, accept: &dyn Fn(&[usize]) -> bool) {
+        if self.right[0] == 0 {
+            if *found == 0 {
+                solution.clone_from(partial);
+            }
+            *found += 1;
+            return;
+        }
+        // the column with the fewest rows
+        let mut c = self.right[0];
+        let mut header = c;
+        while header != 0 {
+            if self.size[header] < self.size[c] {
+                c = header;
+            }
+            header = self.right[header];
+        }
+        if self.size[c] == 0 {
+            return;
+        }
+
+        self.cover(c);
+        let mut nodes = Vec::with_capacity(self.size[c]);
+        let mut node = self.down[c];
+        while node != c {
+            nodes.push(node);
+            node = self.down[node];
+        }
+        if let Some(rng) = rng.as_mut() {
+            nodes.shuffle(rng);
+        }
+        for node in nodes {
+            partial.push(self.row[node]);
+            let mut j = self.right[node];
+            while j != node {
+                self.cover(self.col[j]);
+                j = self.right[j];
+            }
+            if accept(partial) {
+                self.search_from(limit, found, partial, solution, rng.as_mut().map(|rng| &mut **rng as &mut dyn RngCore), accept);
+            }
+            let mut j = self.left[node];
+            while j != node {
+                self.uncover(self.col[j]);
+                j = self.left[j];
+            }
+            partial.pop();
+            if *found >= limit {
+                break;
+            }
+        }
+        self.uncover(c);
+    }
+
+    fn cover(&mut self, c: usize) {
+        self.right[self.left[c]] = self.right[c];
+        self.left[self.right[c]] = self.left[c];
+        let mut i = self.down[c];
+        while i != c {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.col[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, c: usize) {
+        let mut i = self.up[c];
+        while i != c {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.col[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        self.right[self.left[c]] = c;
+        self.left[self.right[c]] = c;
+    }
+}
+
+/// Solves Sudokus as an exact cover problem with Dancing Links.
+/// Every `(cell, digit)` pair is a row that covers the cell and the digit in every unit of the cell,
+/// see [Sudoku::units]. Units with fewer cells than digits, like cages, are secondary columns.
+/// Constraints beyond the units, like the sum of a cage, are checked after every chosen row but not propagated,
+/// so Killer Sudokus with few givens take much longer than with [Backtracking](super::solver::Backtracking).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DancingLinks;
+
+impl DancingLinks {
+    /// Returns the cover matrix with the givens already selected,
+    /// or `None` if two givens collide or break a constraint.
+    fn matrix(sudoku: &Sudoku) -> Option<Matrix> {
+        let size = sudoku.size();
+        let (n, cells) = (size.side(), size.cells());
+        let units = sudoku.units();
+        let (complete, partial): (Vec<_>, Vec<_>) = units.iter().partition(|unit| unit.len() == n);
+        // the first column of every unit, the cells come first
+        let mut columns = vec![Vec::new(); cells];
+        for (u, unit) in complete.iter().chain(partial.iter()).enumerate() {
+            for &(r, c) in unit.iter() {
+                columns[r * n + c].push(cells + u * n);
+            }
+        }
+        let mut matrix = Matrix::new(cells + complete.len() * n, partial.len() * n);
+        for (i, unit_columns) in columns.iter().enumerate() {
+            for d in 0..n {
+                // the row index is i * n + d
+                let mut row = vec![i];
+                row.extend(unit_columns.iter().map(|column| column + d));
+                matrix.add_row(&row);
+            }
+        }
+        for (i, &digit) in sudoku.to_cells().iter().enumerate() {
+            if digit != 0 && !matrix.select(i * n + digit as usize - 1) {
+                return None;
+            }
+        }
+        if !sudoku.constraints().iter().all(|constraint| sudoku.check_constraint(constraint)) {
+            return None;
+        }
+        Some(matrix)
+    }
+
+    fn search(sudoku: &Sudoku, limit: usize, rng: Option<&mut dyn RngCore>) -> (usize, Option<Sudoku>) {
+        let mut matrix = match DancingLinks::matrix(sudoku) {
+            Some(matrix) => matrix,
+            None => return (0, None)
+        };
+        let n = sudoku.size().side();
+        let complete = |rows: &[usize]| {
+            let mut solution = sudoku.clone();
+            for &row in rows {
+                let (i, d) = (row / n, row % n);
+                solution.set(i / n, i % n, SudokuValue::try_from(d as i32 + 1).unwrap()).unwrap();
+            }
+            solution
+        };
+        // only the constraints of the last chosen cell can break, the givens are checked by the matrix
+        // and the digits the constraints can't repeat are covered by their units
+        let givens = sudoku.to_cells();
+        let accept = |rows: &[usize]| {
+            let i = rows[rows.len() - 1] / n;
+            // the digit of a cell is its given or the one of the row chosen for it
+            let value = |r: usize, c: usize| match givens[r * n + c] {
+                0 => rows.iter().find(|&&row| row / n == r * n + c).map_or(0, |&row| (row % n) as u8 + 1),
+                given => given
+            };
+            sudoku.constraints().iter()
+                .filter(|constraint| constraint.contains(i / n, i % n))
+                .all(|constraint| constraint.allows(n, value))
+        };
+        let mut rows = Vec::new();
+        let found = matrix.search(limit, &mut rows, rng, &accept);
+        if found == 0 {
+            return (0, None);
+        }
+        (found, Some(complete(&rows)))
+    }
+}
+
+impl Solver for DancingLinks {
+    fn solve(&self, sudoku: &Sudoku) -> Option<Sudoku> {
+        DancingLinks::search(sudoku, 1, None).1
+    }
+
+    fn count(&self, sudoku: &Sudoku, limit: usize) -> usize {
+        DancingLinks::search(sudoku, limit, None).0
+    }
+
+    fn fill(&self, sudoku: &mut Sudoku, rng: &mut dyn RngCore) -> Result<(), SudokuError> {
+        *sudoku = DancingLinks::search(sudoku, 1, Some(rng)).1
+            .ok_or_else(|| SudokuError::Generation("The Sudoku has no solution to fill it with".to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::puzzle_id::PuzzleId;
+    use crate::settings::Settings;
+    use crate::size::Size;
+    use crate::solver::Backtracking;
+    use crate::variant::Variant;
+    use crate::format::Format;
+
+    const PUZZLE: &str = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    fn generated(variant: Variant, size: Size) -> Sudoku {
+        let id = PuzzleId { settings: Settings { variant, size, difficulty: Difficulty::Easy, ..Settings::default() }, seed: 11 };
+        id.generate().unwrap().0
+    }
+
+    /// Returns the puzzle with its first `n` hints removed, so it has more solutions.
+    fn without_hints(sudoku: &Sudoku, n: usize) -> Sudoku {
+        let mut cells = sudoku.to_cells();
+        cells.iter_mut().filter(|digit| **digit != 0).take(n).for_each(|digit| *digit = 0);
+        let mut sudoku = sudoku.clone();
+        sudoku.set_cells(&cells).unwrap();
+        sudoku
+    }
+
+    #[test]
+    fn agrees_with_backtracking() {
+        let puzzle = Sudoku::try_from(PUZZLE).unwrap();
+        let contradiction = Sudoku::try_from(format!("53..7...5{}", &PUZZLE[9..]).as_str()).unwrap();
+        let mut sudokus = vec![puzzle.clone(), without_hints(&puzzle, 8), contradiction, Sudoku::empty(Size::FOUR)];
+        for (variant, size) in [(Variant::Killer, Size::SIX), (Variant::Diagonal, Size::NINE), (Variant::Jigsaw, Size::SIX), (Variant::Thermo, Size::SIX)] {
+            let sudoku = generated(variant, size);
+            sudokus.push(without_hints(&sudoku, 3));
+            sudokus.push(sudoku);
+        }
+        for sudoku in &sudokus {
+            let counts = (DancingLinks.count(sudoku, 20), Backtracking.count(sudoku, 20));
+            assert_eq!(counts.0, counts.1, "{}", Format::Line.write(sudoku));
+            match DancingLinks.solve(sudoku) {
+                Some(solution) => assert!(solution.solved() && solution.constraints() == sudoku.constraints(), "{}", Format::Line.write(sudoku)),
+                None => assert_eq!(counts.0, 0)
+            }
+            if counts.0 == 1 {
+                assert_eq!(DancingLinks.solve(sudoku), Backtracking.solve(sudoku));
+            }
+        }
+    }
+
+    #[test]
+    fn fills_like_backtracking() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        for size in [Size::FOUR, Size::SIX, Size::NINE, Size::TWELVE] {
+            let mut sudoku = Sudoku::empty(size);
+            DancingLinks.fill(&mut sudoku, &mut rng).unwrap();
+            assert!(sudoku.solved(), "{}", size);
+        }
+        let killer = generated(Variant::Killer, Size::SIX);
+        let mut filled = killer.clone();
+        DancingLinks.fill(&mut filled, &mut rng).unwrap();
+        assert_eq!(Some(filled), Backtracking.solve(&killer));
+    }
+}
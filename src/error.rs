@@ -0,0 +1,26 @@
+use std::fmt::Formatter;
+
+/// Errors returned by the engine.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum SudokuError {
+    /// A board, a value or a [PuzzleId](super::puzzle_id::PuzzleId) couldn't be parsed, with the reason.
+    Parse(String),
+    /// The text of a puzzle couldn't be read, with the 1-based line and column the reading stopped at, see [Format](super::format::Format).
+    Syntax { line: usize, column: usize, reason: String },
+    /// No solution or puzzle could be generated, with the reason.
+    Generation(String),
+    /// The `(row, col)` indices aren't part of a board with `side` rows and columns.
+    InvalidIndex { r: usize, c: usize, side: usize },
+}
+
+impl std::fmt::Display for SudokuError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SudokuError::Parse(reason) | SudokuError::Generation(reason) => write!(f, "{}", reason),
+            SudokuError::Syntax { line, column, reason } => write!(f, "Line {}, column {}: {}", line, column, reason),
+            SudokuError::InvalidIndex { r, c, side } => write!(f, "The cell ({}, {}) is not part of the {}x{} board", r, c, side, side)
+        }
+    }
+}
+
+impl std::error::Error for SudokuError {}
@@ -0,0 +1,335 @@
+use itertools::Itertools;
+use super::error::SudokuError;
+use super::size::Size;
+use super::sudoku::Sudoku;
+use super::value::SudokuValue;
+
+// starts the header lines of SadMan files and the comments of collections
+const COMMENT: char = '#';
+const BLANK: char = '.';
+
+/// Text formats a single puzzle is read from and written in, see [read] for collections of puzzles.
+/// Blanks are written as `.`, both `.` and `0` are read as blanks.
+/// Values above 9 are letters like on the board, and the size of the board is taken from the number of cells.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Format {
+    /// Every cell in a single line, row by row, like `53..7....6..195...`.
+    Line,
+    /// A line per row with spaces between the cells and `|` between the squares,
+    /// and lines of `-` and `+` between the rows of squares.
+    Grid,
+    /// SadMan Sudoku `.sdk` files: `#` header lines followed by a line of cells per row.
+    SadMan,
+    /// Simple Sudoku `.ss` files: a line of cells per row with `|` between the squares,
+    /// and lines of `-` between the rows of squares.
+    SimpleSudoku,
+}
+
+impl Format {
+    pub fn all() -> [Format; 4] {
+        [Format::Line, Format::Grid, Format::SadMan, Format::SimpleSudoku]
+    }
+
+    /// Guesses the format of a single puzzle from the way its lines look.
+    pub fn detect(text: &str) -> Format {
+        let lines = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect_vec();
+        let rows = lines.iter().filter(|line| !is_separator(line) && !line.starts_with(COMMENT)).collect_vec();
+        if lines.first().is_some_and(|line| line.starts_with(COMMENT)) {
+            Format::SadMan
+        } else if lines.len() <= 1 {
+            Format::Line
+        } else if rows.iter().any(|row| row.contains(' ')) {
+            Format::Grid
+        } else if lines.len() > rows.len() || rows.iter().any(|row| row.contains('|')) {
+            Format::SimpleSudoku
+        } else {
+            Format::SadMan
+        }
+    }
+
+    /// Reads a single puzzle, only the values of the cells are kept.
+    pub fn read(self, text: &str) -> Result<Sudoku, SudokuError> {
+        match self {
+            Format::Line => {
+                let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+                let (number, line) = lines.next().ok_or_else(|| syntax(0, 0, "The text has no puzzle"))?;
+                if let Some((next, _)) = lines.next() {
+                    return Err(syntax(next, 0, "A single line was expected, a collection has a puzzle per line"));
+                }
+                read_line(line.trim(), number, indent(line))
+            },
+            Format::Grid | Format::SadMan | Format::SimpleSudoku => read_rows(text, self == Format::SadMan)
+        }
+    }
+
+    /// Writes the values of the cells of the puzzle, every line ends with a line break.
+    pub fn write(self, sudoku: &Sudoku) -> String {
+        let size = sudoku.size();
+        let side = size.side();
+        let cells = sudoku.to_cells().into_iter().map(to_char).collect_vec();
+        let rows = cells.chunks(side).collect_vec();
+        // the cells of every square of a row, joined by `cell` and the squares by `square`
+        let row = |row: &[char], cell: &str, square: &str| row.chunks(size.box_cols)
+            .map(|cells| cells.iter().join(cell))
+            .join(square);
+        let mut text = String::new();
+        match self {
+            Format::Line => text.extend(cells.iter()),
+            Format::SadMan => text.extend(rows.iter().map(|cells| format!("{}\n", cells.iter().collect::<String>()))),
+            Format::Grid | Format::SimpleSudoku => {
+                let (cell, square) = if self == Format::Grid { (" ", " | ") } else { ("", "|") };
+                let lines = rows.iter().map(|cells| row(cells, cell, square)).collect_vec();
+                let divider = if self == Format::Grid {
+                    // a `+` under every `|`
+                    lines[0].chars().map(|c| if c == '|' { '+' } else { '-' }).collect()
+                } else {
+                    "-".repeat(lines[0].chars().count())
+                };
+                for (r, line) in lines.iter().enumerate() {
+                    if r > 0 && r % size.box_rows == 0 {
+                        text.push_str(&divider);
+                        text.push('\n');
+                    }
+                    text.push_str(line);
+                    text.push('\n');
+                }
+            }
+        }
+        if self == Format::Line {
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Format::Line         => "Line",
+            Format::Grid         => "Grid",
+            Format::SadMan       => "SadMan Sudoku",
+            Format::SimpleSudoku => "Simple Sudoku"
+        })
+    }
+}
+
+/// Reads every puzzle of the text, guessing its format.
+/// A collection has one puzzle per line in the [Format::Line] format,
+/// with `#` comments on lines of their own or after the puzzle, see [read_collection].
+/// Other texts are a single puzzle in the format [Format::detect] finds.
+pub fn read(text: &str) -> Result<Vec<Sudoku>, SudokuError> {
+    if is_collection(text) {
+        read_collection(text)
+    } else {
+        Ok(vec![Format::detect(text).read(text)?])
+    }
+}
+
+/// Reads a puzzle per line in the [Format::Line] format, skipping empty lines and `#` comments.
+pub fn read_collection(text: &str) -> Result<Vec<Sudoku>, SudokuError> {
+    text.lines().enumerate()
+        .filter_map(|(number, line)| {
+            let (puzzle, start) = strip_comment(line);
+            (!puzzle.is_empty()).then(|| read_line(puzzle, number, start))
+        })
+        .collect()
+}
+
+/// Writes every puzzle on a line of its own in the [Format::Line] format.
+pub fn write_collection(sudokus: &[Sudoku]) -> String {
+    sudokus.iter().map(|sudoku| Format::Line.write(sudoku)).collect()
+}
+
+/// Returns `true` for texts with comments or more than one line, where every line that isn't a comment is a whole board.
+/// The 16 rows of a 16x16 board without a header look like sixteen 4x4 boards, they are read as a single board
+/// unless every row only has the cells of a 4x4 board.
+fn is_collection(text: &str) -> bool {
+    let lines = text.lines().filter(|line| !line.trim().is_empty()).collect_vec();
+    let puzzles = lines.iter().map(|line| strip_comment(line).0.chars().count()).filter(|&len| len > 0).collect_vec();
+    let board = Size::from_side(lines.len()).is_some_and(|size| {
+        let small = Size::all().into_iter().find(|small| small.cells() == size.side());
+        puzzles.len() == lines.len() && puzzles.iter().all(|&len| len == size.side())
+            && !small.is_some_and(|small| lines.iter().all(|line| line.trim().chars().all(|c| from_char(c, small.side()).is_some())))
+    });
+    (lines.len() > 1 || lines.iter().any(|line| line.contains(COMMENT)))
+        && !puzzles.is_empty()
+        && !board
+        && puzzles.iter().all(|&len| Size::all().iter().any(|size| size.cells() == len))
+}
+
+/// Returns the line without its `#` comment and surrounding whitespace, with the index of its first char.
+fn strip_comment(line: &str) -> (&str, usize) {
+    let puzzle = line.split(COMMENT).next().unwrap_or_default();
+    (puzzle.trim(), indent(puzzle))
+}
+
+/// Returns the number of chars before the first one that isn't whitespace.
+fn indent(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).count()
+}
+
+/// Reads a board from a single line of cells, `number` and `start` are the indices of the line and its first cell.
+fn read_line(line: &str, number: usize, start: usize) -> Result<Sudoku, SudokuError> {
+    let len = line.chars().count();
+    let size = Size::all().into_iter().find(|size| size.cells() == len).ok_or_else(|| syntax(number, start,
+        format!("The line has {} cells, a board has {}", len, Size::all().iter().map(|size| size.cells()).join(", "))))?;
+    let cells = line.chars().enumerate()
+        .map(|(i, c)| from_char(c, size.side()).ok_or_else(|| invalid_cell(number, start + i, c, size)))
+        .collect::<Result<Vec<u8>, SudokuError>>()?;
+    let mut sudoku = Sudoku::empty(size);
+    sudoku.set_cells(&cells)?;
+    Ok(sudoku)
+}
+
+/// Reads a board with a line per row. Spaces and `|` are skipped, as well as lines of `-`, `+` and `|` between the rows.
+/// `header` skips the `#` lines before the first row.
+fn read_rows(text: &str, header: bool) -> Result<Sudoku, SudokuError> {
+    // the indices of the lines and the cells of the rows
+    let mut rows: Vec<(usize, Vec<(usize, char)>)> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || is_separator(trimmed) || (header && rows.is_empty() && trimmed.starts_with(COMMENT)) {
+            continue;
+        }
+        let cells = line.chars().enumerate().filter(|&(_, c)| !c.is_whitespace() && c != '|').collect_vec();
+        if let Some((_, first)) = rows.first().filter(|(_, first)| first.len() != cells.len()) {
+            // the first cell too many, or the end of a row that is too short
+            let column = cells.get(first.len()).map_or(line.chars().count(), |&(column, _)| column);
+            return Err(syntax(number, column, format!("The row has {} cells, the first row has {}", cells.len(), first.len())));
+        }
+        rows.push((number, cells));
+    }
+    let (first_line, first) = rows.first().ok_or_else(|| syntax(0, 0, "The text has no puzzle"))?;
+    let size = Size::from_side(first.len()).ok_or_else(|| syntax(*first_line, first[0].0,
+        format!("A row of {} cells doesn't fit any board, a row has {} cells", first.len(), Size::all().iter().map(|size| size.side()).join(", "))))?;
+    if rows.len() != size.side() {
+        let &(last, _) = rows.last().unwrap();
+        let line = if rows.len() > size.side() { rows[size.side()].0 } else { last + 1 };
+        return Err(syntax(line, 0, format!("The board has {} rows, a {} board has {}", rows.len(), size, size.side())));
+    }
+    let cells = rows.iter()
+        .flat_map(|(number, row)| row.iter().map(move |&(column, c)| (*number, column, c)))
+        .map(|(number, column, c)| from_char(c, size.side()).ok_or_else(|| invalid_cell(number, column, c, size)))
+        .collect::<Result<Vec<u8>, SudokuError>>()?;
+    let mut sudoku = Sudoku::empty(size);
+    sudoku.set_cells(&cells)?;
+    Ok(sudoku)
+}
+
+/// Returns `true` for the lines between the rows of squares, like `------+-------+------`.
+fn is_separator(line: &str) -> bool {
+    line.contains('-') && line.chars().all(|c| matches!(c, '-' | '+' | '|') || c.is_whitespace())
+}
+
+/// Returns the digit of a cell, `0` for a blank, `None` for chars that aren't a value of a board with `side` rows.
+pub(crate) fn from_char(c: char, side: usize) -> Option<u8> {
+    match c {
+        BLANK => Some(0),
+        _ => SudokuValue::from_char(c).map(|val| val as u8).filter(|&digit| digit as usize <= side)
+    }
+}
+
+/// Returns the char of a digit like on the board, `.` for a blank.
+pub(crate) fn to_char(digit: u8) -> char {
+    match SudokuValue::try_from(digit as i32) {
+        Ok(SudokuValue::Empty) | Err(_) => BLANK,
+        Ok(val) => val.to_string().chars().next().unwrap()
+    }
+}
+
+fn invalid_cell(line: usize, column: usize, c: char, size: Size) -> SudokuError {
+    let biggest = to_char(size.side() as u8);
+    syntax(line, column, format!("{} is not a cell of a {} board, a cell is {}, 0 or 1 to {}", c, size, BLANK, biggest))
+}
+
+/// Creates a [SudokuError::Syntax] from 0-based indices.
+fn syntax<S: Into<String>>(line: usize, column: usize, reason: S) -> SudokuError {
+    SudokuError::Syntax { line: line + 1, column: column + 1, reason: reason.into() }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use super::*;
+
+    const PUZZLE: &str = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    /// A solved board with every other cell blanked, so both blanks and every value are written.
+    fn board(size: Size) -> Sudoku {
+        let mut sudoku = Sudoku::empty(size);
+        sudoku.fill(&mut ChaCha8Rng::seed_from_u64(7)).unwrap();
+        let cells = sudoku.to_cells().into_iter().enumerate().map(|(i, digit)| if i % 3 == 0 { 0 } else { digit }).collect_vec();
+        sudoku.set_cells(&cells).unwrap();
+        sudoku
+    }
+
+    #[test]
+    fn round_trips_every_format() {
+        for size in Size::all() {
+            let sudoku = board(size);
+            for format in Format::all() {
+                let text = format.write(&sudoku);
+                assert_eq!(Format::detect(&text), format, "{} {}:\n{}", size, format, text);
+                assert_eq!(format.read(&text), Ok(sudoku.clone()), "{} {}:\n{}", size, format, text);
+                assert_eq!(read(&text), Ok(vec![sudoku.clone()]), "{} {}:\n{}", size, format, text);
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_collections() {
+        let sudokus = vec![board(Size::NINE), Sudoku::try_from(PUZZLE).unwrap(), board(Size::FOUR)];
+        let text = write_collection(&sudokus);
+        assert_eq!(read_collection(&text), Ok(sudokus.clone()));
+        assert_eq!(read(&text), Ok(sudokus));
+    }
+
+    #[test]
+    fn reads_collections_with_as_many_puzzles_as_cells() {
+        // 81 lines of 81 cells, or 16 lines of 16 cells, aren't the rows of a board
+        for (size, count) in [(Size::NINE, 81), (Size::FOUR, 16), (Size::SIX, 36)] {
+            let sudokus = vec![board(size); count];
+            assert_eq!(read(&write_collection(&sudokus)), Ok(sudokus), "{}", size);
+        }
+        let sixteen = board(Size::SIXTEEN);
+        assert_eq!(read(&Format::SadMan.write(&sixteen)), Ok(vec![sixteen]));
+    }
+
+    #[test]
+    fn writes_the_usual_layouts() {
+        let sudoku = Sudoku::try_from(PUZZLE).unwrap();
+        assert_eq!(Format::Line.write(&sudoku), format!("{}\n", PUZZLE));
+        assert!(Format::Grid.write(&sudoku).starts_with("5 3 . | . 7 . | . . .\n6 . . | 1 9 5 | . . .\n. 9 8 | . . . | . 6 .\n------+-------+------\n"));
+        assert!(Format::SimpleSudoku.write(&sudoku).starts_with("53.|.7.|...\n6..|195|...\n.98|...|.6.\n-----------\n"));
+        assert!(Format::SadMan.write(&sudoku).starts_with("53..7....\n6..195...\n"));
+    }
+
+    #[test]
+    fn reads_blanks_headers_and_comments() {
+        let sudoku = Sudoku::try_from(PUZZLE).unwrap();
+        assert_eq!(Sudoku::try_from(PUZZLE.replace('.', "0").as_str()), Ok(sudoku.clone()));
+        let sdk = format!("#AAuthor\n#DDescription\n{}", Format::SadMan.write(&sudoku));
+        assert_eq!(Format::detect(&sdk), Format::SadMan);
+        assert_eq!(read(&sdk), Ok(vec![sudoku.clone()]));
+        let collection = format!("# puzzles\n\n{}  # first\n  {}\n", PUZZLE, PUZZLE.replace('.', "0"));
+        assert_eq!(read(&collection), Ok(vec![sudoku.clone(), sudoku]));
+    }
+
+    #[test]
+    fn reports_lines_and_columns() {
+        let syntax = |text: &str| match read(text) {
+            Err(SudokuError::Syntax { line, column, .. }) => (line, column),
+            other => panic!("expected a syntax error, got {:?}", other)
+        };
+        assert_eq!(syntax(&PUZZLE.replacen('.', "x", 1)), (1, 3));
+        assert_eq!(syntax(&PUZZLE[1..]), (1, 1));
+        assert_eq!(syntax(&format!("# puzzles\n{}\n  {}", PUZZLE, PUZZLE.replacen('7', "?", 1))), (3, 7));
+        let grid = Format::Grid.write(&Sudoku::try_from(PUZZLE).unwrap()).replacen('6', "Z", 1);
+        assert_eq!(syntax(&grid), (2, 1));
+        let short = Format::SimpleSudoku.write(&Sudoku::try_from(PUZZLE).unwrap()).replacen("195", "19", 1);
+        assert_eq!(syntax(&short), (2, 11));
+        let rows = Format::SadMan.write(&Sudoku::try_from(PUZZLE).unwrap());
+        assert_eq!(syntax(&rows[..rows.len() - 10]), (9, 1));
+    }
+}
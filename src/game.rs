@@ -0,0 +1,173 @@
+use super::puzzle_id::PuzzleId;
+#[cfg(feature = "serde")]
+use super::board::Board;
+#[cfg(feature = "serde")]
+use super::error::SudokuError;
+#[cfg(feature = "serde")]
+use super::value::SudokuValue;
+
+/// The state of a game in progress or a finished one, for a [Sudoku](super::sudoku::Sudoku)
+/// or a [Samurai](super::samurai::Samurai) board.
+/// When it's deserialized the boards are checked to belong to each other.
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize),
+           serde(try_from = "GameData<B>", bound(deserialize = "B: Board + serde::Deserialize<'de>")))]
+pub struct Game<B> {
+    /// The id the puzzle was generated from, `None` for a puzzle that was read from a file.
+    pub id: Option<PuzzleId>,
+    /// The hints of the puzzle.
+    pub given: B,
+    /// The hints and the values entered so far.
+    pub current: B,
+    pub solution: B,
+    /// Cells whose value was checked and found wrong, row-major.
+    pub wrong: Vec<(usize, usize)>,
+    /// Pairs of cells found breaking a rule, like two cells a knight's move apart with the same digit.
+    pub conflicts: Vec<((usize, usize), (usize, usize))>,
+    /// Seconds played so far.
+    pub elapsed: u64,
+}
+
+impl<B: Clone> Game<B> {
+    /// Starts a game of the puzzle without any values entered.
+    pub fn new(id: Option<PuzzleId>, given: B, solution: B) -> Self {
+        Game { id, current: given.clone(), given, solution, wrong: Vec::new(), conflicts: Vec::new(), elapsed: 0 }
+    }
+}
+
+/// How a [Game] is deserialized, before the boards are checked to belong to each other.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GameData<B> {
+    id: Option<PuzzleId>,
+    given: B,
+    current: B,
+    solution: B,
+    wrong: Vec<(usize, usize)>,
+    conflicts: Vec<((usize, usize), (usize, usize))>,
+    elapsed: u64,
+}
+
+#[cfg(feature = "serde")]
+impl<B: Board> TryFrom<GameData<B>> for Game<B> {
+    type Error = SudokuError;
+
+    /// Checks that the boards have the same size, that the current board keeps every hint
+    /// and that the wrong and conflicting cells are part of the board.
+    fn try_from(data: GameData<B>) -> Result<Self, Self::Error> {
+        let layout = data.given.layout();
+        if [&data.current, &data.solution].iter().any(|board| (board.layout().size, board.layout().side) != (layout.size, layout.side)) {
+            return Err(SudokuError::Parse(format!("The boards of the game don't all have the size {}", layout.size)));
+        }
+        for (r, c) in (0..layout.side).flat_map(|r| (0..layout.side).map(move |c| (r, c))) {
+            match data.given.get(r, c) {
+                Some(&given) if given != SudokuValue::Empty && data.current.get(r, c) != Some(&given) => {
+                    return Err(SudokuError::Parse(format!("The hint {} in row {}, column {} is missing from the current board", given, r + 1, c + 1)));
+                },
+                _ => {}
+            }
+        }
+        let cells = data.wrong.iter().chain(data.conflicts.iter().flat_map(|(a, b)| [a, b]));
+        if let Some(&(r, c)) = cells.into_iter().find(|&&(r, c)| data.given.get(r, c).is_none()) {
+            return Err(SudokuError::InvalidIndex { r, c, side: layout.side });
+        }
+        let GameData { id, given, current, solution, wrong, conflicts, elapsed } = data;
+        Ok(Game { id, given, current, solution, wrong, conflicts, elapsed })
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use std::convert::TryFrom;
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::regions::Regions;
+    use crate::samurai::{self, Samurai};
+    use crate::settings::Settings;
+    use crate::size::Size;
+    use crate::sudoku::Sudoku;
+    use crate::value::SudokuValue;
+    use crate::variant::Variant;
+
+    fn game(variant: Variant, size: Size) -> Game<Sudoku> {
+        let id = PuzzleId { settings: Settings { variant, size, difficulty: Difficulty::Easy, ..Settings::default() }, seed: 7 };
+        let (given, solution) = id.generate().unwrap();
+        // the first empty cell gets a value that's checked and found wrong
+        let side = size.side();
+        let (r, c) = (0..side).flat_map(|r| (0..side).map(move |c| (r, c)))
+            .find(|&(r, c)| given.get(r, c) == Some(&SudokuValue::Empty)).unwrap();
+        let wrong = if solution.get(r, c) == Some(&SudokuValue::One) { SudokuValue::Two } else { SudokuValue::One };
+        let mut game = Game::new(Some(id), given, solution);
+        game.current.set(r, c, wrong).unwrap();
+        game.wrong.push((r, c));
+        game.elapsed = 83;
+        game
+    }
+
+    #[test]
+    fn round_trips_games() {
+        for (variant, size) in [(Variant::Classic, Size::NINE), (Variant::Jigsaw, Size::SIX), (Variant::Killer, Size::FOUR)] {
+            let game = game(variant, size);
+            let json = serde_json::to_string(&game).unwrap();
+            assert_eq!(serde_json::from_str::<Game<Sudoku>>(&json).unwrap(), game, "{}", json);
+        }
+    }
+
+    #[test]
+    fn rejects_games_whose_boards_dont_belong_together() {
+        let parse = |game: &Game<Sudoku>| serde_json::from_str::<Game<Sudoku>>(&serde_json::to_string(game).unwrap());
+        let valid = game(Variant::Classic, Size::FOUR);
+        assert_eq!(parse(&valid).unwrap(), valid);
+
+        let mut smaller = valid.clone();
+        smaller.solution = Sudoku::empty(Size::SIX);
+        assert!(parse(&smaller).is_err());
+        let (r, c) = (0..4).flat_map(|r| (0..4).map(move |c| (r, c)))
+            .find(|&(r, c)| valid.given.get(r, c) != Some(&SudokuValue::Empty)).unwrap();
+        let mut missing = valid.clone();
+        missing.current.set(r, c, SudokuValue::Empty).unwrap();
+        assert!(parse(&missing).unwrap_err().to_string().contains("is missing from the current board"));
+        let mut changed = valid.clone();
+        changed.current.set(r, c, if valid.given.get(r, c) == Some(&SudokuValue::One) { SudokuValue::Two } else { SudokuValue::One }).unwrap();
+        assert!(parse(&changed).is_err());
+        let mut wrong = valid.clone();
+        wrong.wrong.push((4, 0));
+        assert!(parse(&wrong).is_err());
+        let mut conflicts = valid;
+        conflicts.conflicts.push(((0, 0), (0, 4)));
+        assert!(parse(&conflicts).is_err());
+    }
+
+    #[test]
+    fn writes_boards_as_strings() {
+        let puzzle = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let sudoku = Sudoku::try_from(puzzle).unwrap();
+        assert_eq!(serde_json::to_string(&sudoku).unwrap(), format!(r#"{{"cells":"{}"}}"#, puzzle));
+        assert_eq!(serde_json::to_string(&SudokuValue::Nine).unwrap(), "9");
+        assert_eq!(serde_json::to_string(&Difficulty::Hard).unwrap(), r#""Hard""#);
+        let game = game(Variant::Classic, Size::FOUR);
+        let json = serde_json::to_string(&game).unwrap();
+        assert!(json.starts_with(r#"{"id":"E4-"#), "{}", json);
+        let (r, c) = game.wrong[0];
+        assert!(json.ends_with(&format!(r#""wrong":[[{},{}]],"conflicts":[],"elapsed":83}}"#, r, c)), "{}", json);
+    }
+
+    #[test]
+    fn rejects_invalid_boards() {
+        assert!(serde_json::from_str::<Sudoku>(r#"{"cells":"123"}"#).is_err());
+        assert!(serde_json::from_str::<Sudoku>(r#"{"cells":"1...............","regions":"1122112233443345"}"#).is_err());
+        // the second region of the first rows is split in two
+        let disconnected = serde_json::from_str::<Sudoku>(r#"{"cells":"1...............","regions":"1122211233443344"}"#);
+        assert!(disconnected.unwrap_err().to_string().starts_with("Region 2 isn't connected"));
+        let nine = format!(r#"{{"cells":"1...............","regions":"{}"}}"#, serde_json::to_string(&Regions::squares(Size::NINE)).unwrap().trim_matches('"'));
+        assert!(serde_json::from_str::<Sudoku>(&nine).is_err());
+        let outside = r#"{"cells":"................","constraints":[{"Inequality":{"cells":[[0,0],[4,0]]}}]}"#;
+        assert!(serde_json::from_str::<Sudoku>(outside).is_err());
+        let samurai = serde_json::to_string(&Samurai::empty()).unwrap();
+        assert_eq!(serde_json::from_str::<Samurai>(&samurai).unwrap(), Samurai::empty());
+        // r1c10 lies between the two top grids
+        let gap = format!(r#"{{"cells":"{}5{}"}}"#, ".".repeat(9), ".".repeat(samurai::SIDE * samurai::SIDE - 10));
+        assert_eq!(serde_json::from_str::<Samurai>(&gap).unwrap_err().to_string(),
+                   "r1c10 lies between the grids of the Samurai and can't have a value");
+    }
+}
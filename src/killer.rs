@@ -0,0 +1,90 @@
+use rand::Rng;
+use rand::seq::{IteratorRandom, SliceRandom};
+use super::constraint::Constraint;
+use super::sudoku::Sudoku;
+use super::value::SudokuValue;
+use super::regions::neighbours;
+
+// number of cells a cage grows to, if its neighbours allow it
+const MIN_CAGE: usize = 2;
+const MAX_CAGE: usize = 4;
+
+/// Splits a solved board into cages of orthogonally connected cells without repeated digits.
+/// Every cell is in exactly one cage and the sum of a cage is the sum of its solved digits.
+pub fn cages<R: Rng>(solution: &Sudoku, rng: &mut R) -> Vec<Constraint> {
+    let side = solution.size().side();
+    let value = |(r, c): (usize, usize)| *solution.get(r, c).unwrap();
+    let mut caged = vec![vec![false; side]; side];
+    let mut starts: Vec<(usize, usize)> = (0..side).flat_map(|r| (0..side).map(move |c| (r, c))).collect();
+    starts.shuffle(rng);
+
+    let mut cages = Vec::new();
+    for start in starts {
+        if caged[start.0][start.1] {
+            continue;
+        }
+        let target = rng.gen_range(MIN_CAGE..=MAX_CAGE);
+        let mut cells = vec![start];
+        caged[start.0][start.1] = true;
+        while cells.len() < target {
+            let values: Vec<SudokuValue> = cells.iter().map(|&cell| value(cell)).collect();
+            let next = cells.iter()
+                .flat_map(|&(r, c)| neighbours(r, c, side))
+                .filter(|&(r, c)| !caged[r][c] && !values.contains(&value((r, c))))
+                .choose(rng);
+            match next {
+                Some((r, c)) => {
+                    caged[r][c] = true;
+                    cells.push((r, c));
+                },
+                None => break
+            }
+        }
+        cells.sort_unstable();
+        let sum = cells.iter().map(|&cell| value(cell) as u32).sum();
+        cages.push(Constraint::Cage { cells, sum });
+    }
+    cages.sort_unstable();
+    cages
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use itertools::Itertools;
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::settings::Settings;
+    use crate::size::Size;
+    use crate::solver::Uniqueness;
+    use crate::variant::Variant;
+
+    #[test]
+    fn cages_cover_every_cell_with_the_sums_of_the_solution() {
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        for size in [Size::SIX, Size::NINE] {
+            let mut solution = Sudoku::empty(size);
+            solution.fill(&mut rng).unwrap();
+            let cages = cages(&solution, &mut rng);
+            let cells = cages.iter().flat_map(|cage| cage.cells().iter().copied()).sorted().collect_vec();
+            assert_eq!(cells, (0..size.side()).cartesian_product(0..size.side()).collect_vec(), "{}", size);
+            for cage in &cages {
+                let Constraint::Cage { cells, sum } = cage else { panic!("{:?} is not a cage", cage) };
+                let digits = cells.iter().map(|&(r, c)| *solution.get(r, c).unwrap() as u32).collect_vec();
+                assert_eq!(digits.iter().sum::<u32>(), *sum);
+                assert!(digits.iter().all_unique());
+                assert!(cage.allows(size.side(), |r, c| *solution.get(r, c).unwrap() as u8));
+            }
+        }
+    }
+
+    #[test]
+    fn generates_unique_puzzles() {
+        let settings = Settings { variant: Variant::Killer, size: Size::SIX, difficulty: Difficulty::Easy, ..Settings::default() };
+        let (puzzle, solution) = Sudoku::new(&settings, 5).unwrap();
+        assert_eq!(puzzle.uniqueness(), Uniqueness::Unique);
+        assert_eq!(puzzle.solution(), Some(solution));
+        assert!(puzzle.constraints().iter().all(|constraint| matches!(constraint, Constraint::Cage { .. })));
+    }
+}
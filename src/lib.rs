@@ -0,0 +1,35 @@
+//! The engine of the Sudoku game: generating, solving and rating Sudokus of every size and variant.
+//! The terminal game in `main.rs` is built on top of it.
+
+pub mod error;
+pub mod difficulty;
+pub mod sudoku;
+pub mod solver;
+// alternative solver backend, the game itself only uses solver::Backtracking
+pub mod dlx;
+pub mod logic;
+pub mod value;
+pub mod puzzle_id;
+pub mod symmetry;
+pub mod settings;
+pub mod size;
+pub mod constraint;
+pub mod variant;
+// generators of the constraints of the variants, used through variant::Variant
+pub(crate) mod killer;
+pub(crate) mod markings;
+pub(crate) mod sandwich;
+pub(crate) mod regions;
+pub(crate) mod chess;
+pub mod samurai;
+pub mod board;
+pub mod format;
+pub mod game;
+
+pub use error::SudokuError;
+pub use difficulty::Difficulty;
+pub use sudoku::Sudoku;
+pub use value::SudokuValue;
+pub use game::Game;
+pub use chess::Piece;
+pub use regions::Regions;
@@ -0,0 +1,684 @@
+use std::convert::TryFrom;
+use std::fmt::Formatter;
+use itertools::Itertools;
+use super::sudoku::Sudoku;
+use super::value::SudokuValue;
+use super::size::Size;
+use super::constraint::Constraint;
+
+/// Techniques used by the [LogicalSolver], ordered from the easiest to the hardest.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Hash)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    /// Removes candidates that no combination of digits in a [Constraint::Cage] can use.
+    KillerCage,
+    /// Removes candidates that don't increase along a [Constraint::Thermo].
+    Thermometer,
+    /// Removes candidates that no sum along a [Constraint::Arrow] can use.
+    Arrow,
+    /// Removes candidates that no digit on the other side of a [Constraint::Kropki] dot fits.
+    Kropki,
+    /// Removes candidates that no order of the digits of a row or column with a [Constraint::Sandwich] clue can use.
+    Sandwich,
+    /// Removes candidates that no digit on the other side of a [Constraint::Inequality] sign fits.
+    Inequality,
+    /// Removes candidates that no digit of the neighbour fits, with or without a [Constraint::Consecutive] bar.
+    Consecutive,
+    /// Removes the odd candidates of an even [Constraint::Parity] cell and the even ones of an odd cell.
+    Parity,
+    PointingPair,
+    BoxLineReduction,
+    NakedPair,
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    NakedQuad,
+    HiddenQuad,
+    XWing,
+    Swordfish,
+    XYWing,
+}
+
+impl Technique {
+    /// Returns every technique in the order the [LogicalSolver] tries them.
+    pub fn all() -> [Technique; 21] {
+        [Technique::NakedSingle, Technique::HiddenSingle, Technique::KillerCage, Technique::Thermometer, Technique::Arrow, Technique::Kropki,
+         Technique::Sandwich, Technique::Inequality, Technique::Consecutive, Technique::Parity,
+         Technique::PointingPair, Technique::BoxLineReduction,
+         Technique::NakedPair, Technique::HiddenPair, Technique::NakedTriple, Technique::HiddenTriple,
+         Technique::NakedQuad, Technique::HiddenQuad, Technique::XWing, Technique::Swordfish, Technique::XYWing]
+    }
+}
+
+impl std::fmt::Display for Technique {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Technique::NakedSingle      => "Naked Single",
+            Technique::HiddenSingle     => "Hidden Single",
+            Technique::KillerCage       => "Killer Cage",
+            Technique::Thermometer      => "Thermometer",
+            Technique::Arrow            => "Arrow",
+            Technique::Kropki           => "Kropki",
+            Technique::Sandwich         => "Sandwich",
+            Technique::Inequality       => "Inequality",
+            Technique::Consecutive      => "Consecutive",
+            Technique::Parity           => "Parity",
+            Technique::PointingPair     => "Pointing Pair",
+            Technique::BoxLineReduction => "Box/Line Reduction",
+            Technique::NakedPair        => "Naked Pair",
+            Technique::HiddenPair       => "Hidden Pair",
+            Technique::NakedTriple      => "Naked Triple",
+            Technique::HiddenTriple     => "Hidden Triple",
+            Technique::NakedQuad        => "Naked Quad",
+            Technique::HiddenQuad       => "Hidden Quad",
+            Technique::XWing            => "X-Wing",
+            Technique::Swordfish        => "Swordfish",
+            Technique::XYWing           => "XY-Wing"
+        })
+    }
+}
+
+/// A single deduction of the [LogicalSolver].
+/// Cells are `(row, col)` indices.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Step {
+    pub technique: Technique,
+    /// The cells that form the pattern of the technique.
+    pub cells: Vec<(usize, usize)>,
+    /// The values this step places.
+    pub placements: Vec<(usize, usize, SudokuValue)>,
+    /// The candidates this step removes.
+    pub eliminations: Vec<(usize, usize, SudokuValue)>,
+}
+
+/// Prints the step like `Naked Pair r1c2 r1c5 => r1c7-3 r1c8-3`, with 1-based indices.
+/// Placements are written as `r3c5=7`, eliminations as `r1c7-3`.
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.technique)?;
+        for (r, c) in &self.cells {
+            write!(f, " r{}c{}", r + 1, c + 1)?;
+        }
+        write!(f, " =>")?;
+        for (r, c, val) in &self.placements {
+            write!(f, " r{}c{}={}", r + 1, c + 1, val)?;
+        }
+        for (r, c, val) in &self.eliminations {
+            write!(f, " r{}c{}-{}", r + 1, c + 1, val)?;
+        }
+        Ok(())
+    }
+}
+
+/// Solves a Sudoku like a human would, by applying named [Technique]s one [Step] at a time.
+/// Every empty cell keeps a bitmask of its candidates, bit `d - 1` is set if the digit `d` is possible.
+pub struct LogicalSolver {
+    size: Size,
+    // mask with a bit for every digit of the board
+    all: u32,
+    values: Vec<u8>,
+    candidates: Vec<u32>,
+    // the rows, the columns, the regions and then the other units of [Sudoku::units] as row-major cell indices
+    units: Vec<Vec<usize>>,
+    // the units every cell is part of
+    cell_units: Vec<Vec<usize>>,
+    constraints: Vec<Constraint>,
+}
+
+impl LogicalSolver {
+    pub fn new(sudoku: &Sudoku) -> Self {
+        let size = sudoku.size();
+        let n = size.side();
+        let units: Vec<Vec<usize>> = sudoku.units().iter()
+            .map(|unit| unit.iter().map(|&(r, c)| r * n + c).collect())
+            .collect();
+        let mut cell_units = vec![Vec::new(); size.cells()];
+        for (u, unit) in units.iter().enumerate() {
+            for &i in unit {
+                cell_units[i].push(u);
+            }
+        }
+
+        let all = (1 << n) - 1;
+        let mut solver = LogicalSolver {
+            size, all, values: vec![0; size.cells()], candidates: vec![all; size.cells()],
+            units, cell_units, constraints: sudoku.constraints().to_vec()
+        };
+        for i in 0..size.cells() {
+            let val = *sudoku.get(i / n, i % n).unwrap();
+            if val != SudokuValue::Empty {
+                solver.place(i, val as u8);
+            }
+        }
+        solver
+    }
+
+    /// Finds the next step with the easiest possible technique and applies it.
+    /// Returns `None` if the Sudoku is solved or none of the techniques makes progress.
+    pub fn next_step(&mut self) -> Option<Step> {
+        let step = Technique::all().iter().find_map(|&technique| self.find(technique))?;
+        let n = self.size.side();
+        for &(r, c, val) in &step.placements {
+            self.place(r * n + c, val as u8);
+        }
+        for &(r, c, val) in &step.eliminations {
+            self.candidates[r * n + c] &= !bit(val as u8);
+        }
+        Some(step)
+    }
+
+    /// Applies steps until the Sudoku is solved or the solver is stuck, and returns all of them.
+    pub fn solve(&mut self) -> Vec<Step> {
+        let mut steps = Vec::new();
+        while let Some(step) = self.next_step() {
+            steps.push(step);
+        }
+        steps
+    }
+
+    /// Returns `true` if every cell has a value.
+    pub fn solved(&self) -> bool {
+        self.values.iter().all(|&digit| digit != 0)
+    }
+
+    fn find(&self, technique: Technique) -> Option<Step> {
+        match technique {
+            Technique::NakedSingle      => self.naked_single(),
+            Technique::HiddenSingle     => self.hidden_single(),
+            Technique::KillerCage | Technique::Thermometer | Technique::Arrow | Technique::Kropki
+            | Technique::Sandwich | Technique::Inequality | Technique::Consecutive | Technique::Parity => self.constraint(technique),
+            Technique::PointingPair     => self.pointing(),
+            Technique::BoxLineReduction => self.box_line_reduction(),
+            Technique::NakedPair        => self.naked_subset(2, technique),
+            Technique::HiddenPair       => self.hidden_subset(2, technique),
+            Technique::NakedTriple      => self.naked_subset(3, technique),
+            Technique::HiddenTriple     => self.hidden_subset(3, technique),
+            Technique::NakedQuad        => self.naked_subset(4, technique),
+            Technique::HiddenQuad       => self.hidden_subset(4, technique),
+            Technique::XWing            => self.fish(2, technique),
+            Technique::Swordfish        => self.fish(3, technique),
+            Technique::XYWing           => self.xy_wing()
+        }
+    }
+
+    /// A cell with only one candidate.
+    fn naked_single(&self) -> Option<Step> {
+        (0..self.size.cells()).find(|&i| self.values[i] == 0 && self.candidates[i].count_ones() == 1)
+            .map(|i| Step {
+                technique: Technique::NakedSingle,
+                cells: vec![self.cell(i)],
+                placements: vec![self.placement(i, self.candidates[i].trailing_zeros() as u8 + 1)],
+                eliminations: Vec::new(),
+            })
+    }
+
+    /// A digit that fits in only one cell of a unit.
+    fn hidden_single(&self) -> Option<Step> {
+        for unit in self.complete_units() {
+            for digit in self.digits() {
+                let positions = self.positions(unit, digit);
+                if positions.len() == 1 {
+                    return Some(Step {
+                        technique: Technique::HiddenSingle,
+                        cells: vec![self.cell(positions[0])],
+                        placements: vec![self.placement(positions[0], digit)],
+                        eliminations: Vec::new(),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// All candidates of a digit in a square lie on one line, so the rest of the line can't have it.
+    fn pointing(&self) -> Option<Step> {
+        let n = self.size.side();
+        self.intersection(Technique::PointingPair, 2 * n..3 * n)
+    }
+
+    /// All candidates of a digit in a line lie in one square, so the rest of the square can't have it.
+    /// Other units that contain every digit are used like lines.
+    fn box_line_reduction(&self) -> Option<Step> {
+        let n = self.size.side();
+        self.intersection(Technique::BoxLineReduction, (0..2 * n).chain(3 * n..self.units.len()))
+    }
+
+    /// All candidates of a digit in one of the `bases` lie in another unit,
+    /// so the rest of the other unit can't have it.
+    fn intersection<I: Iterator<Item = usize>>(&self, technique: Technique, bases: I) -> Option<Step> {
+        for base in bases {
+            let unit = &self.units[base];
+            if unit.len() != self.size.side() {
+                continue;
+            }
+            for digit in self.digits() {
+                let positions = self.positions(unit, digit);
+                if positions.len() < 2 {
+                    continue;
+                }
+                for &other in &self.cell_units[positions[0]] {
+                    let cover = &self.units[other];
+                    if other == base || !positions.iter().all(|i| cover.contains(i)) {
+                        continue;
+                    }
+                    let others = cover.iter().copied().filter(|i| !unit.contains(i)).collect_vec();
+                    if let Some(step) = self.eliminating(technique, &positions, &others, bit(digit)) {
+                        return Some(step);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// `n` cells of a unit that together have only `n` candidates.
+    fn naked_subset(&self, n: usize, technique: Technique) -> Option<Step> {
+        for unit in &self.units {
+            let empty = unit.iter().copied()
+                .filter(|&i| self.values[i] == 0 && self.candidates[i].count_ones() as usize <= n)
+                .collect_vec();
+            for subset in empty.into_iter().combinations(n) {
+                let mask = subset.iter().fold(0, |acc, &i| acc | self.candidates[i]);
+                if mask.count_ones() as usize != n {
+                    continue;
+                }
+                let others = unit.iter().copied().filter(|i| !subset.contains(i)).collect_vec();
+                if let Some(step) = self.eliminating(technique, &subset, &others, mask) {
+                    return Some(step);
+                }
+            }
+        }
+        None
+    }
+
+    /// `n` digits that fit in only `n` cells of a unit, so these cells can't have other candidates.
+    fn hidden_subset(&self, n: usize, technique: Technique) -> Option<Step> {
+        for unit in self.complete_units() {
+            let digits = self.digits().filter(|&d| !self.positions(unit, d).is_empty()).collect_vec();
+            if digits.len() <= n {
+                continue;
+            }
+            for subset in digits.into_iter().combinations(n) {
+                let mask = subset.iter().fold(0, |acc, &d| acc | bit(d));
+                let cells = unit.iter().copied()
+                    .filter(|&i| self.values[i] == 0 && self.candidates[i] & mask != 0)
+                    .collect_vec();
+                if cells.len() != n {
+                    continue;
+                }
+                if let Some(step) = self.eliminating(technique, &cells, &cells, self.all & !mask) {
+                    return Some(step);
+                }
+            }
+        }
+        None
+    }
+
+    /// `n` lines in which a digit fits only into the same `n` crossing lines,
+    /// so the crossing lines can't have the digit anywhere else.
+    /// `n = 2` is an X-Wing and `n = 3` a Swordfish.
+    fn fish(&self, n: usize, technique: Technique) -> Option<Step> {
+        let side = self.size.side();
+        for digit in self.digits() {
+            for row in [true, false] {
+                let cross = |i: usize| if row { i % side } else { i / side };
+                let bases = (0..side)
+                    .map(|l| self.positions(&line(self.size, l, row), digit))
+                    .filter(|positions| (2..=n).contains(&positions.len()))
+                    .collect_vec();
+                for subset in bases.iter().combinations(n) {
+                    let crossing = subset.iter().flat_map(|positions| positions.iter().map(|&i| cross(i)))
+                        .unique().collect_vec();
+                    if crossing.len() != n {
+                        continue;
+                    }
+                    let pattern = subset.iter().flat_map(|positions| positions.iter().copied()).collect_vec();
+                    let others = crossing.iter().flat_map(|&l| line(self.size, l, !row))
+                        .filter(|i| !pattern.contains(i)).collect_vec();
+                    if let Some(step) = self.eliminating(technique, &pattern, &others, bit(digit)) {
+                        return Some(step);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// A pivot cell with the candidates `xy` that sees two pincers with `xz` and `yz`.
+    /// One of the pincers has to be `z`, so cells seeing both pincers can't have `z`.
+    fn xy_wing(&self) -> Option<Step> {
+        let pairs = (0..self.size.cells()).filter(|&i| self.values[i] == 0 && self.candidates[i].count_ones() == 2).collect_vec();
+        for &pivot in &pairs {
+            let xy = self.candidates[pivot];
+            for &a in pairs.iter().filter(|&&a| self.sees(pivot, a)) {
+                let shared = self.candidates[a] & xy;
+                if shared.count_ones() != 1 {
+                    continue;
+                }
+                let z = self.candidates[a] & !xy;
+                let yz = (xy & !shared) | z;
+                for &b in pairs.iter().filter(|&&b| b != a && self.sees(pivot, b) && self.candidates[b] == yz) {
+                    let others = (0..self.size.cells()).filter(|&i| i != pivot && self.sees(i, a) && self.sees(i, b)).collect_vec();
+                    if let Some(step) = self.eliminating(Technique::XYWing, &[pivot, a, b], &others, z) {
+                        return Some(step);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// A candidate of a cell in a constraint that no combination of the candidates
+    /// of the other cells allows, for example a digit too big for the sum of a cage.
+    fn constraint(&self, technique: Technique) -> Option<Step> {
+        let n = self.size.side();
+        for constraint in self.constraints.iter().filter(|&constraint| constraint_technique(constraint) == Some(technique)) {
+            let cells = constraint.cells().iter().map(|&(r, c)| r * n + c).collect_vec();
+            let mut supported = vec![0; cells.len()];
+            let complete = match constraint {
+                Constraint::Sandwich { sum, .. } => self.sandwich_support(&cells, *sum, &mut supported),
+                _ => self.support(constraint, &cells, &mut vec![0; cells.len()], &mut supported)
+            };
+            if complete {
+                continue;
+            }
+            let mut eliminations = Vec::new();
+            for (k, &i) in cells.iter().enumerate() {
+                if self.values[i] != 0 {
+                    continue;
+                }
+                for digit in self.digits() {
+                    if self.candidates[i] & !supported[k] & bit(digit) != 0 {
+                        eliminations.push(self.placement(i, digit));
+                    }
+                }
+            }
+            if !eliminations.is_empty() {
+                let cells = cells.iter().map(|&i| self.cell(i)).collect();
+                return Some(Step { technique, cells, placements: Vec::new(), eliminations });
+            }
+        }
+        None
+    }
+
+    /// Tries every combination of candidates for the cells of a constraint
+    /// and marks the digits of the combinations the constraint allows in `supported`.
+    /// Returns `true` and stops early once every candidate is supported, as nothing can be eliminated then.
+    fn support(&self, constraint: &Constraint, cells: &[usize], assigned: &mut Vec<u8>, supported: &mut [u32]) -> bool {
+        let k = assigned.iter().take_while(|&&digit| digit != 0).count();
+        if k == cells.len() {
+            for (mask, &digit) in supported.iter_mut().zip(assigned.iter()) {
+                *mask |= bit(digit);
+            }
+            return self.all_supported(cells, supported);
+        }
+        let n = self.size.side();
+        let options = match self.values[cells[k]] {
+            0 => self.candidates[cells[k]],
+            digit => bit(digit)
+        };
+        for digit in self.digits().filter(|&digit| options & bit(digit) != 0) {
+            // the same digit can't be used twice by cells that see each other
+            if (0..k).any(|j| assigned[j] == digit && self.sees(cells[j], cells[k])) {
+                continue;
+            }
+            assigned[k] = digit;
+            let value = |r: usize, c: usize| cells.iter().position(|&i| i == r * n + c).map_or(0, |j| assigned[j]);
+            let done = constraint.allows(n, value) && self.support(constraint, cells, assigned, supported);
+            assigned[k] = 0;
+            if done {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Marks the digits a row or column with a [Constraint::Sandwich] clue allows in `supported`, like [LogicalSolver::support].
+    /// Trying every order of a whole line is too slow, so only the positions of the 1 and the biggest digit and the sets
+    /// of digits between them that add up to the sum are tried. A set supports the candidates of the cells between
+    /// the ends that are in it, and the rest of the digits support the candidates of the cells outside.
+    fn sandwich_support(&self, cells: &[usize], sum: u32, supported: &mut [u32]) -> bool {
+        let n = cells.len();
+        let biggest = n as u8;
+        let options = cells.iter().map(|&i| match self.values[i] {
+            0 => self.candidates[i],
+            digit => bit(digit)
+        }).collect_vec();
+        let middle = self.all & !bit(1) & !bit(biggest);
+        for (p, q) in (0..n).tuple_combinations() {
+            for (first, last) in [(1, biggest), (biggest, 1)] {
+                if options[p] & bit(first) == 0 || options[q] & bit(last) == 0 {
+                    continue;
+                }
+                let (between, outside): (Vec<usize>, Vec<usize>) = (0..n).filter(|&k| k != p && k != q).partition(|&k| k > p && k < q);
+                for set in (2..biggest).combinations(between.len()).filter(|set| set.iter().map(|&digit| digit as u32).sum::<u32>() == sum) {
+                    let inside = set.iter().fold(0, |mask, &digit| mask | bit(digit));
+                    let between_options = between.iter().map(|&k| options[k]).collect_vec();
+                    let outside_options = outside.iter().map(|&k| options[k]).collect_vec();
+                    if !assignable(&between_options, inside) || !assignable(&outside_options, middle & !inside) {
+                        continue;
+                    }
+                    supported[p] |= bit(first);
+                    supported[q] |= bit(last);
+                    between.iter().for_each(|&k| supported[k] |= options[k] & inside);
+                    outside.iter().for_each(|&k| supported[k] |= options[k] & middle & !inside);
+                }
+            }
+        }
+        self.all_supported(cells, supported)
+    }
+
+    /// Returns `true` if every candidate of the empty cells is marked in `supported`.
+    fn all_supported(&self, cells: &[usize], supported: &[u32]) -> bool {
+        cells.iter().zip(supported.iter()).all(|(&i, &mask)| self.values[i] != 0 || self.candidates[i] & !mask == 0)
+    }
+
+    /// Creates a step that removes the digits in `mask` from the `targets`,
+    /// or `None` if none of the targets have any of these candidates.
+    fn eliminating(&self, technique: Technique, pattern: &[usize], targets: &[usize], mask: u32) -> Option<Step> {
+        let mut eliminations = Vec::new();
+        for &i in targets {
+            if self.values[i] != 0 {
+                continue;
+            }
+            for digit in self.digits() {
+                if self.candidates[i] & mask & bit(digit) != 0 {
+                    eliminations.push(self.placement(i, digit));
+                }
+            }
+        }
+        if eliminations.is_empty() {
+            None
+        } else {
+            Some(Step { technique, cells: pattern.iter().map(|&i| self.cell(i)).collect(), placements: Vec::new(), eliminations })
+        }
+    }
+
+    /// Returns the empty cells of the unit that have the digit as candidate.
+    fn positions(&self, unit: &[usize], digit: u8) -> Vec<usize> {
+        unit.iter().copied().filter(|&i| self.values[i] == 0 && self.candidates[i] & bit(digit) != 0).collect()
+    }
+
+    fn place(&mut self, i: usize, digit: u8) {
+        self.values[i] = digit;
+        self.candidates[i] = 0;
+        for &u in &self.cell_units[i] {
+            for &j in &self.units[u] {
+                self.candidates[j] &= !bit(digit);
+            }
+        }
+    }
+
+    /// Returns the units that have to contain every digit.
+    fn complete_units(&self) -> impl Iterator<Item = &Vec<usize>> {
+        let n = self.size.side();
+        self.units.iter().filter(move |unit| unit.len() == n)
+    }
+
+    /// Returns every digit of the board, from 1 to its side length.
+    fn digits(&self) -> std::ops::RangeInclusive<u8> {
+        1..=self.size.side() as u8
+    }
+
+    fn cell(&self, i: usize) -> (usize, usize) {
+        (i / self.size.side(), i % self.size.side())
+    }
+
+    fn placement(&self, i: usize, digit: u8) -> (usize, usize, SudokuValue) {
+        let (r, c) = self.cell(i);
+        (r, c, SudokuValue::try_from(digit as i32).unwrap())
+    }
+
+    /// Returns `true` if two different cells share a unit.
+    fn sees(&self, i: usize, j: usize) -> bool {
+        i != j && self.cell_units[i].iter().any(|u| self.cell_units[j].contains(u))
+    }
+}
+
+fn bit(digit: u8) -> u32 {
+    1 << (digit - 1)
+}
+
+/// Returns `true` if every cell can get a different digit of `digits` out of its `options`.
+fn assignable(options: &[u32], digits: u32) -> bool {
+    match options.split_first() {
+        None => true,
+        Some((&first, rest)) => {
+            let mut choices = first & digits;
+            while choices != 0 {
+                let choice = choices & choices.wrapping_neg();
+                choices &= !choice;
+                if assignable(rest, digits & !choice) {
+                    return true;
+                }
+            }
+            false
+        }
+    }
+}
+
+/// Returns the technique that uses the constraint,
+/// `None` for constraints that only add a unit, like an extra region.
+fn constraint_technique(constraint: &Constraint) -> Option<Technique> {
+    match constraint {
+        Constraint::Cage { .. } => Some(Technique::KillerCage),
+        Constraint::Thermo { .. } => Some(Technique::Thermometer),
+        Constraint::Arrow { .. } => Some(Technique::Arrow),
+        Constraint::Kropki { .. } => Some(Technique::Kropki),
+        Constraint::Sandwich { .. } => Some(Technique::Sandwich),
+        Constraint::Inequality { .. } => Some(Technique::Inequality),
+        Constraint::Consecutive { .. } => Some(Technique::Consecutive),
+        Constraint::Parity { .. } => Some(Technique::Parity),
+        Constraint::Region { .. } | Constraint::Chess { .. } => None
+    }
+}
+
+/// Returns the row (`row == true`) or the column with index `n`.
+fn line(size: Size, n: usize, row: bool) -> Vec<usize> {
+    let side = size.side();
+    (0..side).map(|m| if row { n * side + m } else { m * side + n }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Uniqueness;
+    use crate::difficulty::Rating;
+
+    // the first grid of Project Euler problem 96, solved with singles alone
+    const SINGLES: &str = "003020600900305001001806400008102900700000008006708200002609500800203009005010300";
+    // the examples of the techniques on sudokuwiki.org
+    const POINTING_PAIR: &str = ".179.36......8....9.....5.7.72.1.43....4.2.7..6437.25.7.1....65....3......56.172.";
+    const BOX_LINE_REDUCTION: &str = ".16..78.3.9.8.....87...1.6..48...3..65...9.82.39...65..6.9...2..8...29369246..51.";
+    const NAKED_TRIPLE: &str = ".....1.3.231.9.....65..31..6789243..1.3.5...6...1367....936.57...6.198433........";
+    const HIDDEN_TRIPLE: &str = "300000000970010000600583000200000900500621003008000005000435002000090056000000001";
+    const NAKED_QUAD: &str = "....3..86....2..4..9..7852.3718562949..1423754..3976182..7.3859.392.54677..9.4132";
+    const HIDDEN_QUAD: &str = "901500046425090081860010020502000000019000460600000002196040253200060817000001694";
+    const X_WING: &str = "1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5";
+    const SWORDFISH: &str = "52941.7.3..6..3..2..32......523...76637.5.2..19.62753.3...6942.2..83.6..96.7423.5";
+    const XY_WING: &str = "9..24.....5.69.231.2..5..9..9.7..32...29356.7.7...29...69.2..7351..79.622.7.86..9";
+    // Arto Inkala's "world's hardest Sudoku", far beyond an XY-Wing
+    const BEYOND: &str = "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+
+    fn sudoku(puzzle: &str) -> Sudoku {
+        let sudoku = Sudoku::try_from(puzzle).unwrap();
+        assert_eq!(sudoku.uniqueness(), Uniqueness::Unique, "{}", puzzle);
+        sudoku
+    }
+
+    /// Checks that no step of the trace places a wrong digit or removes the right one.
+    fn assert_sound(sudoku: &Sudoku, steps: &[Step]) {
+        let solution = sudoku.solution().unwrap();
+        for step in steps {
+            assert!(step.placements.iter().all(|&(r, c, val)| solution.get(r, c) == Some(&val)), "{}", step);
+            assert!(step.eliminations.iter().all(|&(r, c, val)| solution.get(r, c) != Some(&val)), "{}", step);
+        }
+    }
+
+    /// Returns the first step of the technique in the trace of the puzzle.
+    fn first(puzzle: &str, technique: Technique) -> String {
+        let sudoku = sudoku(puzzle);
+        let steps = LogicalSolver::new(&sudoku).solve();
+        assert_sound(&sudoku, &steps);
+        steps.iter().find(|step| step.technique == technique).unwrap_or_else(|| panic!("no {} in {}", technique, puzzle)).to_string()
+    }
+
+    /// Returns the step of the technique once the singles of the puzzle are placed,
+    /// for the examples the solver gets through with easier techniques.
+    fn after_singles(puzzle: &str, technique: Technique) -> String {
+        let sudoku = sudoku(puzzle);
+        let mut solver = LogicalSolver::new(&sudoku);
+        while solver.find(Technique::NakedSingle).or_else(|| solver.find(Technique::HiddenSingle)).is_some() {
+            solver.next_step();
+        }
+        let step = solver.find(technique).unwrap_or_else(|| panic!("no {} in {}", technique, puzzle));
+        assert_sound(&sudoku, std::slice::from_ref(&step));
+        step.to_string()
+    }
+
+    #[test]
+    fn solves_with_singles() {
+        let sudoku = sudoku(SINGLES);
+        let mut solver = LogicalSolver::new(&sudoku);
+        let steps = solver.solve();
+        assert!(solver.solved());
+        assert_sound(&sudoku, &steps);
+        assert!(steps.iter().all(|step| step.technique <= Technique::HiddenSingle && step.placements.len() == 1 && step.cells.len() == 1));
+        assert_eq!(first(SINGLES, Technique::NakedSingle), "Naked Single r5c6 => r5c6=4");
+        assert_eq!(first(X_WING, Technique::HiddenSingle), "Hidden Single r8c2 => r8c2=7");
+    }
+
+    #[test]
+    fn finds_intersections() {
+        assert_eq!(first(POINTING_PAIR, Technique::PointingPair), "Pointing Pair r2c7 r2c9 => r2c1-3 r2c2-3 r2c3-3");
+        assert_eq!(first(BOX_LINE_REDUCTION, Technique::BoxLineReduction), "Box/Line Reduction r1c4 r1c5 => r2c5-2 r3c4-2 r3c5-2");
+    }
+
+    #[test]
+    fn finds_subsets() {
+        assert_eq!(first(POINTING_PAIR, Technique::NakedPair), "Naked Pair r4c1 r4c4 => r4c6-5 r4c6-8 r4c9-8");
+        assert_eq!(first(X_WING, Technique::HiddenPair), "Hidden Pair r4c8 r5c8 => r4c8-3 r4c8-7 r5c8-3 r5c8-7 r5c8-9");
+        assert_eq!(first(NAKED_TRIPLE, Technique::NakedTriple), "Naked Triple r1c1 r1c2 r1c5 => r1c7-9 r1c9-8 r1c9-9");
+        assert_eq!(after_singles(HIDDEN_TRIPLE, Technique::HiddenTriple), "Hidden Triple r1c3 r1c7 r1c8 => r1c3-4 r1c7-4 r1c7-6 r1c7-7 r1c8-4 r1c8-6 r1c8-7 r1c8-9");
+        assert_eq!(after_singles(NAKED_QUAD, Technique::NakedQuad), "Naked Quad r1c1 r2c1 r2c2 r3c1 => r1c2-1 r1c2-5 r1c3-5 r2c3-5 r2c3-6 r2c3-8 r3c3-6");
+        assert_eq!(first(HIDDEN_QUAD, Technique::HiddenQuad), "Hidden Quad r4c4 r4c6 r6c4 r6c6 => r4c4-3 r4c4-8 r4c6-3 r4c6-8 r6c4-3 r6c4-8 r6c6-3 r6c6-5 r6c6-8");
+    }
+
+    #[test]
+    fn finds_fish_and_wings() {
+        assert_eq!(first(X_WING, Technique::XWing), "X-Wing r2c4 r2c8 r6c4 r6c8 => r1c4-7 r5c4-7 r8c4-7 r9c4-7 r8c8-7 r9c8-7");
+        assert_eq!(first(SWORDFISH, Technique::Swordfish), "Swordfish r2c1 r3c1 r4c1 r2c5 r3c5 r4c5 r2c7 r3c7 r4c7 => r2c2-8 r2c8-8 r3c2-8 r3c9-8 r4c6-8");
+        assert_eq!(first(XY_WING, Technique::XYWing), "XY-Wing r1c2 r2c3 r9c2 => r8c3-4");
+    }
+
+    #[test]
+    fn stops_without_a_wrong_placement() {
+        let sudoku = sudoku(BEYOND);
+        let mut solver = LogicalSolver::new(&sudoku);
+        let steps = solver.solve();
+        assert!(!solver.solved());
+        assert_sound(&sudoku, &steps);
+        assert_eq!(Rating::from_steps(&steps, solver.solved()).difficulty(), None);
+    }
+}
@@ -3,59 +3,130 @@ use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
 use crossterm::{execute, queue, event::KeyCode, cursor, style, ErrorKind};
 use style::Attribute::{Underlined, NoUnderline};
 use rand::seq::SliceRandom;
+use itertools::Itertools;
 
-mod util;
-mod difficulty;
 mod ui;
-mod sudoku;
-mod value;
+mod util;
+mod save;
+mod cli;
+
+use sudoku_game::{difficulty, logic, value, puzzle_id, settings, constraint, variant, board, Game, Piece};
 
 use util::*;
+use ui::{read_key_code, is_up, is_left, is_down, is_right, key_code_to_sudoku_value};
 use difficulty::*;
-use sudoku::Sudoku;
+use board::{Board, Layout};
+use puzzle_id::PuzzleId;
+use settings::Settings;
+use variant::Variant;
+use constraint::{Constraint, Dot, Parity, Shape};
+use logic::Step;
 use value::SudokuValue;
-use std::collections::HashSet;
+use save::Saved;
+use cli::Command;
+use sudoku_game::sudoku::Sudoku;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 
 const MENU: &str = r#"
         Welcome to the Sudoku Game in Rust!
                 by Talalaiko Kiril 
                     <40618094>
-                    
                       q : quit
                  up / w : up
                left / a : left
                down / s : down
               right / d : right
-                    0-9 : 0-9
+               0-9, A-P : values
                       c : check
                       t : tip
+                      i : enter puzzle ID
+                      r : continue saved game
+                      m : minimal puzzle
+                      b : board size
+                      v : variant
+                      n : anti-knight
+                      k : anti-king
                   Enter : Select
                  Escape : Main Menu
 
-                 Select Difficulty
+          Select Difficulty and Symmetry
+
 "#;
 const CHECK_KEY: char = 'c';
 const HINTS_KEY: char = 't';
+const PUZZLE_ID_KEY: char = 'i';
+const CONTINUE_KEY: char = 'r';
+const MINIMAL_KEY: char = 'm';
+const SIZE_KEY: char = 'b';
+const VARIANT_KEY: char = 'v';
+// rows and columns kept between the selected cell and the edge of the terminal when a big board is moved
+const SCROLL_MARGIN: i32 = 2;
+const ANTI_KNIGHT_KEY: char = 'n';
+const ANTI_KING_KEY: char = 'k';
 
 mod color {
     use crossterm::style;
     pub const GIVEN_NUMBER: style::Color    = style::Color::Blue;
     pub const INSERTED_NUMBER: style::Color = style::Color::Yellow;
     pub const WRONG_NUMBER: style::Color    = style::Color::Red;
+    // dark backgrounds of the Killer cages, touching cages get different ones
+    pub const CAGES: [style::Color; 8] = [
+        style::Color::AnsiValue(52), style::Color::AnsiValue(22), style::Color::AnsiValue(58), style::Color::AnsiValue(53),
+        style::Color::AnsiValue(23), style::Color::AnsiValue(94), style::Color::AnsiValue(237), style::Color::AnsiValue(60)
+    ];
+    pub const DIAGONAL: style::Color = style::Color::AnsiValue(238);
+    pub const WINDOW: style::Color   = style::Color::AnsiValue(23);
+    // backgrounds of the thermometer cells and its bulb, the line between them is drawn in the color of the bulb
+    pub const THERMO: style::Color   = style::Color::AnsiValue(238);
+    pub const BULB: style::Color     = style::Color::AnsiValue(243);
+    // background of the circle of an arrow and the color of its line
+    pub const ARROW: style::Color    = style::Color::AnsiValue(24);
+    pub const DOT: style::Color      = style::Color::White;
+    pub const SIGN: style::Color     = style::Color::White;
+    pub const BAR: style::Color      = style::Color::White;
+    // backgrounds of the even and odd cells, light enough apart to tell them from each other and dark enough for the numbers
+    pub const EVEN: style::Color     = style::Color::AnsiValue(241);
+    pub const ODD: style::Color      = style::Color::AnsiValue(236);
 }
 
 fn main() -> Result<(), ErrorKind> {
-    let stdout = Arc::new(Mutex::new(io::stdout()));
-    run(stdout)
+    let command = match Command::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    match command {
+        Command::Play { difficulty, seed, puzzle } => match cli::first_game(difficulty, seed, puzzle) {
+            Ok(first) => run(Arc::new(Mutex::new(io::stdout())), first),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        },
+        command => if cli::run(command)? { Ok(()) } else { std::process::exit(1) }
+    }
 }
 
-fn run<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>) -> Result<(), ErrorKind> {
+/// Shows the menu until it's quit, after playing the `first` game if there is one.
+fn run<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>, first: Option<Game<Sudoku>>) -> Result<(), ErrorKind> {
     // setup
     crossterm::terminal::enable_raw_mode()?;
     queue!(w.lock().unwrap(), crossterm::terminal::EnterAlternateScreen, cursor::Hide)?;
-    let mut selected = Difficulty::Medium;
+    let mut settings = Settings::default();
+    // shown below the menu, e.g. an invalid puzzle ID
+    let mut message: Option<String> = None;
     let size = crossterm::terminal::size()?;
     let term = Arc::new(Mutex::new(ui::Terminal::new(size.0, size.1)));
+    if let Some(game) = first {
+        message = resume(Arc::clone(&w), Arc::clone(&term), Saved::from(game))?;
+    }
     loop {
         let size = crossterm::terminal::size()?;
         let mut t_lock = term.lock().unwrap();
@@ -65,27 +136,101 @@ fn run<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>) -> Result<(), ErrorKind>
         for (n, line) in MENU.split('\n').enumerate() {
             queue!(w_lock, style::Print(line), style::Print('\n'),cursor::MoveTo(t_lock.h_center()-25, t_lock.v_center_str(MENU)+n as u16))?;
         }
-        queue!(w_lock, cursor::MoveTo(t_lock.h_center_str(&Difficulty::Hard.to_string()), t_lock.v_center() + 7), style::Print(
-            format!("{}{}{}", match selected {Difficulty::Hard => Underlined, _ => NoUnderline} , Difficulty::Hard, NoUnderline)),
-                  cursor::MoveTo(t_lock.h_center_str(&Difficulty::Medium.to_string()), t_lock.v_center() + 8), style::Print(
-            format!("{}{}{}", match selected {Difficulty::Medium => Underlined, _ => NoUnderline} , Difficulty::Medium, NoUnderline)),
-                  cursor::MoveTo(t_lock.h_center_str(&Difficulty::Easy.to_string()), t_lock.v_center() + 9), style::Print(
-            format!("{}{}{}", match selected {Difficulty::Easy => Underlined, _ => NoUnderline} , Difficulty::Easy, NoUnderline)))?;
+        let symmetry_text = format!("< {} >", settings.symmetry);
+        let minimal_text = format!("[{}] minimal", if settings.minimal { 'x' } else { ' ' });
+        let size_text = format!("Board {}", settings.size);
+        let variant_text = format!("Variant {}", settings.variant);
+        let chess_text = Piece::all().iter().map(|&piece| format!("[{}] {}", if settings.has(piece) { 'x' } else { ' ' }, piece.to_string().to_lowercase())).join(" ");
+        queue!(w_lock, cursor::MoveTo(t_lock.h_center_str(&symmetry_text), t_lock.v_center() + 12), style::Print(symmetry_text),
+                       cursor::MoveTo(t_lock.h_center_str(&minimal_text), t_lock.v_center() + 13), style::Print(minimal_text),
+                       cursor::MoveTo(t_lock.h_center_str(&size_text), t_lock.v_center() + 14), style::Print(size_text),
+                       cursor::MoveTo(t_lock.h_center_str(&variant_text), t_lock.v_center() + 15), style::Print(variant_text),
+                       cursor::MoveTo(t_lock.h_center_str(&chess_text), t_lock.v_center() + 16), style::Print(chess_text))?;
+        if let Some(message) = &message {
+            queue!(w_lock, cursor::MoveTo(t_lock.h_center_str(message), t_lock.v_center() + 17), style::Print(message))?;
+        }
+        queue!(w_lock, cursor::MoveTo(t_lock.h_center_str(&Difficulty::Hard.to_string()), t_lock.v_center() + 9), style::Print(
+            format!("{}{}{}", match settings.difficulty {Difficulty::Hard => Underlined, _ => NoUnderline} , Difficulty::Hard, NoUnderline)),
+                  cursor::MoveTo(t_lock.h_center_str(&Difficulty::Medium.to_string()), t_lock.v_center() + 10), style::Print(
+            format!("{}{}{}", match settings.difficulty {Difficulty::Medium => Underlined, _ => NoUnderline} , Difficulty::Medium, NoUnderline)),
+                  cursor::MoveTo(t_lock.h_center_str(&Difficulty::Easy.to_string()), t_lock.v_center() + 11), style::Print(
+            format!("{}{}{}", match settings.difficulty {Difficulty::Easy => Underlined, _ => NoUnderline} , Difficulty::Easy, NoUnderline)))?;
         w_lock.flush()?;
         drop(w_lock);
         drop(t_lock);
 
         let key_code = read_key_code()?;
+        message = None;
         if key_code == KeyCode::Char('q') {
             break;
         } else if is_up(key_code) {
-            selected = selected.up();
+            settings.difficulty = settings.difficulty.up();
         } else if is_down(key_code) {
-            selected = selected.down();
+            settings.difficulty = settings.difficulty.down();
+        } else if is_left(key_code) {
+            settings.symmetry = settings.symmetry.previous();
+        } else if is_right(key_code) {
+            settings.symmetry = settings.symmetry.next();
+        } else if key_code == KeyCode::Char(MINIMAL_KEY) {
+            if settings.supports_minimal() {
+                settings.minimal = !settings.minimal;
+            } else if settings.size.supports_minimal() {
+                message = Some(format!("Minimal puzzles are not supported for {} Sudoku", settings.variant));
+            } else {
+                message = Some(format!("Minimal puzzles are not supported for {} boards", settings.size));
+            }
+        } else if key_code == KeyCode::Char(SIZE_KEY) {
+            settings.size = settings.size.next();
+            if !settings.variant.supports(settings.size) {
+                settings.variant = Variant::Classic;
+            }
+            settings.minimal &= settings.supports_minimal();
+            settings.remove_unsupported_pieces();
+        } else if key_code == KeyCode::Char(VARIANT_KEY) {
+            let variant = settings.variant.next();
+            if variant.supports(settings.size) {
+                settings.variant = variant;
+            } else {
+                settings.variant = Variant::Classic;
+                message = Some(format!("{} Sudoku is not supported for {} boards", variant, settings.size));
+            }
+            settings.minimal &= settings.supports_minimal();
+            settings.remove_unsupported_pieces();
+        } else if let Some(piece) = match key_code {
+            KeyCode::Char(ANTI_KNIGHT_KEY) => Some(Piece::Knight),
+            KeyCode::Char(ANTI_KING_KEY) => Some(Piece::King),
+            _ => None
+        } {
+            if Piece::supports(settings.size, settings.variant) {
+                settings.set(piece, !settings.has(piece));
+            } else {
+                message = Some(format!("{} is not supported for {} Sudoku on {} boards", piece, settings.variant, settings.size));
+            }
         } else if key_code == KeyCode::Enter {
             let w = Arc::clone(&w);
             let term = Arc::clone(&term);
-            game(w, term, selected)?;
+            message = game(w, term, PuzzleId::random(settings))?;
+        } else if key_code == KeyCode::Char(PUZZLE_ID_KEY) {
+            let typed = read_text(&mut *w.lock().unwrap(), &term.lock().unwrap(), "Puzzle ID: ")?;
+            match typed.as_deref().map(PuzzleId::try_from) {
+                Some(Ok(id)) => {
+                    let w = Arc::clone(&w);
+                    let term = Arc::clone(&term);
+                    message = game(w, term, id)?;
+                },
+                Some(Err(err)) => message = Some(err.to_string()),
+                None => {}
+            }
+        } else if key_code == KeyCode::Char(CONTINUE_KEY) {
+            match save::load() {
+                Ok(Some(saved)) => {
+                    let w = Arc::clone(&w);
+                    let term = Arc::clone(&term);
+                    message = resume(w, term, saved)?;
+                },
+                Ok(None) => message = Some("There is no saved game".to_string()),
+                Err(err) => message = Some(format!("The saved game couldn't be read: {}", err))
+            }
         }
     };
 
@@ -96,28 +241,87 @@ fn run<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>) -> Result<(), ErrorKind>
     Ok(())
 }
 
-fn game<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>, term: Arc<Mutex<ui::Terminal>>, diff: Difficulty) -> Result<(), ErrorKind> {
-    // setup
+/// Reads a line of text below the menu, returns `None` if it's cancelled with Escape.
+fn read_text<W: io::Write>(w: &mut W, term: &ui::Terminal, prompt: &str) -> Result<Option<String>, ErrorKind> {
+    let mut text = String::new();
+    loop {
+        let line = format!("{}{}_", prompt, text);
+        queue!(w, cursor::MoveTo(0, term.v_center() + 17), crossterm::terminal::Clear(crossterm::terminal::ClearType::CurrentLine),
+               cursor::MoveTo(term.h_center_str(&line), term.v_center() + 17), style::Print(line))?;
+        w.flush()?;
+        match read_key_code()? {
+            KeyCode::Enter => return Ok(Some(text)),
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Backspace => { text.pop(); },
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+    }
+}
+
+/// Generates and plays the puzzle of the id, returns the message shown in the menu if it couldn't be generated or saved.
+fn game<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>, term: Arc<Mutex<ui::Terminal>>, id: PuzzleId) -> Result<Option<String>, ErrorKind> {
     // clear
-    let mut w_lock = w.lock().unwrap();
-    queue!(w_lock, cursor::Hide, crossterm::terminal::Clear(crossterm::terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+    queue!(w.lock().unwrap(), cursor::Hide, crossterm::terminal::Clear(crossterm::terminal::ClearType::All), cursor::MoveTo(0, 0))?;
 
-    // draw Sudoku Lines
-    draw_sudoku_lines(&mut *w_lock, &*term.lock().unwrap())?;
-    drop(w_lock);
+    // create the boards, a Samurai is made of five Sudokus
+    let played = if id.settings.variant == Variant::Samurai {
+        id.generate_samurai().map(|(given, solution)| play(w, term, Game::new(Some(id), given, solution)))
+    } else {
+        id.generate().map(|(given, solution)| play(w, term, Game::new(Some(id), given, solution)))
+    };
+    match played {
+        Ok(result) => result,
+        Err(err) => Ok(Some(err.to_string()))
+    }
+}
+
+/// Plays the saved game from where it was left, returns the message shown in the menu if it couldn't be saved again.
+fn resume<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>, term: Arc<Mutex<ui::Terminal>>, saved: Saved) -> Result<Option<String>, ErrorKind> {
+    // clear
+    queue!(w.lock().unwrap(), cursor::Hide, crossterm::terminal::Clear(crossterm::terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+    match saved {
+        Saved::Sudoku(game) => play(w, term, *game),
+        Saved::Samurai(game) => play(w, term, *game)
+    }
+}
+
+/// Plays the game until it's left, an unfinished game is saved to be continued from the menu and a solved one removes the save.
+fn play<W: 'static + io::Write + Send, B: Board>(w: Arc<Mutex<W>>, term: Arc<Mutex<ui::Terminal>>, game: Game<B>) -> Result<Option<String>, ErrorKind>
+    where Saved: From<Game<B>> {
+    let Game { id, given, mut current, solution, wrong, mut conflicts, elapsed } = game;
+    // setup
+    let rating = given.rating();
+    let hints = given.hints();
+    // generated puzzles are minimal if they were minimized, only a puzzle read from a file is checked,
+    // as proving that every hint is needed takes long
+    let minimal = match id {
+        Some(id) => id.settings.minimal || id.settings.variant.is_minimal(),
+        None => given.layout().size.supports_minimal() && given.is_minimal()
+    };
+    let layout = given.layout();
+    let side = layout.side;
+    let mut selected = (side / 2, side / 2);
+    // boards bigger than the terminal are moved to keep the selected cell visible
+    let mut scroll = board_scroll(&term.lock().unwrap(), layout, selected, (0, 0));
 
-    // create Sudokus
-    let (given, solution) = Sudoku::new(diff as usize);
-    let mut current = given;
+    // draw Sudoku Lines, the regions of some variants are only known after generating
+    let mut w_lock = w.lock().unwrap();
+    draw_sudoku_lines(&mut *w_lock, &term.lock().unwrap(), &given, scroll)?;
+    draw_puzzle_info(&mut *w_lock, &term.lock().unwrap(), layout, id, &rating, hints, minimal)?;
+    drop(w_lock);
+    let backgrounds = constraint_backgrounds(&given);
 
-    let mut selected = (4, 4);
     let mut changed = true;
     let mut win = false;
-    let mut wrong_values: HashSet<(usize, usize)> = HashSet::new();
+    let mut wrong_values: HashSet<(usize, usize)> = wrong.into_iter().collect();
+    let mut tip: Option<Step> = None;
 
     // timer
     let timer_stop = Arc::new(AtomicBool::new(false));
-    let timer = Arc::new(time::Instant::now());
+    // a continued game starts from the time it was left at
+    let now = time::Instant::now();
+    let timer = Arc::new(now.checked_sub(time::Duration::from_secs(elapsed)).unwrap_or(now));
 
     // timer thread
     let timer_handle = {
@@ -126,15 +330,15 @@ fn game<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>, term: Arc<Mutex<ui::Ter
         let w = Arc::clone(&w);
         let term = Arc::clone(&term);
         thread::spawn(move || {
-            let mut last_time = std::u64::MAX;
-            while timer_stop.load(Ordering::SeqCst) == false {
+            let mut last_time = u64::MAX;
+            while !timer_stop.load(Ordering::SeqCst) {
                 let now = timer.elapsed().as_secs();
                 if last_time != now {
                     last_time = now;
                     // draw new time
                     let mut w_lock = w.lock().unwrap();
                     let t_lock = term.lock().unwrap();
-                    draw_time(&mut *w_lock, &*t_lock, now);
+                    draw_time(&mut *w_lock, &t_lock, layout, now);
                     drop(w_lock);
                     drop(t_lock);
                     thread::sleep(time::Duration::from_millis(990));
@@ -149,28 +353,37 @@ fn game<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>, term: Arc<Mutex<ui::Ter
     loop {
         let new_size = crossterm::terminal::size()?;
         let mut t_lock = term.lock().unwrap();
-        if t_lock.set_size(new_size.0, new_size.1) {
+        let resized = t_lock.set_size(new_size.0, new_size.1);
+        let new_scroll = board_scroll(&t_lock, layout, selected, scroll);
+        if resized || new_scroll != scroll {
+            scroll = new_scroll;
             changed = true;
             // redraw lines
             let mut w_lock = w.lock().unwrap();
             queue!(w_lock, crossterm::terminal::Clear(crossterm::terminal::ClearType::All))?;
-            draw_sudoku_lines(&mut *w_lock, &*t_lock)?;
-            draw_time(&mut *w_lock, &* t_lock, timer.elapsed().as_secs());
+            draw_sudoku_lines(&mut *w_lock, &t_lock, &given, scroll)?;
+            draw_puzzle_info(&mut *w_lock, &t_lock, layout, id, &rating, hints, minimal)?;
+            draw_time(&mut *w_lock, &t_lock, layout, timer.elapsed().as_secs());
         }
         if changed {
             let mut w_lock = w.lock().unwrap();
-            queue!(w_lock, cursor::MoveTo(t_lock.h_center()+13, t_lock.v_center()-5), style::Print(time_top_bar()))?;
-            queue!(w_lock, cursor::MoveTo(t_lock.h_center()+13, t_lock.v_center()-4), style::Print(time_bet_bar()))?;
-            queue!(w_lock, cursor::MoveTo(t_lock.h_center()+13, t_lock.v_center()-3), style::Print(time_bot_bar()))?;
-            if current == solution {
+            let info = info_column(&t_lock, layout);
+            queue!(w_lock, cursor::MoveTo(info, t_lock.v_center()-5), style::Print(time_top_bar()))?;
+            queue!(w_lock, cursor::MoveTo(info, t_lock.v_center()-4), style::Print(time_bet_bar()))?;
+            queue!(w_lock, cursor::MoveTo(info, t_lock.v_center()-3), style::Print(time_bot_bar()))?;
+            if current.solved() {
                 win = true;
                 timer_stop.store(true, Ordering::SeqCst);
-                queue!(w_lock, cursor::MoveTo(t_lock.h_center()+14, t_lock.v_center()-4), style::Print("Done!"))?;
+                queue!(w_lock, cursor::MoveTo(info+1, t_lock.v_center()-4), style::Print("Done!"))?;
             } else {
                 let count = current.count(SudokuValue::Empty);
-                queue!(w_lock, cursor::MoveTo(t_lock.h_center()+15, t_lock.v_center()-4), style::Print(format!("{:2}", count)))?;
+                queue!(w_lock, cursor::MoveTo(info+1, t_lock.v_center()-4), style::Print(format!("{:3}", count)))?;
             }
-            draw_sudoku_values(&mut *w_lock, &*t_lock, &current, &given, selected, win, &wrong_values)?;
+            // both cells of a conflict are flagged, even a given one
+            let flagged: HashSet<(usize, usize)> = wrong_values.iter().copied().chain(conflicts.iter().flat_map(|&(a, b)| [a, b])).collect();
+            draw_sudoku_values(&mut *w_lock, &t_lock, &current, &given, &backgrounds, selected, win, &flagged, scroll)?;
+            draw_constraint_info(&mut *w_lock, &t_lock, &given, selected)?;
+            draw_tip(&mut *w_lock, &t_lock, layout, tip.as_ref())?;
             changed = false;
         }
         drop(t_lock);
@@ -182,44 +395,64 @@ fn game<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>, term: Arc<Mutex<ui::Ter
             timer_handle.join().unwrap();
             break;
         } else if is_up(key_code) {
-            selected.0 = selected.0.saturating_sub(1);
+            selected = move_selection(&given, selected, (-1, 0));
             changed = true;
         } else if is_left(key_code) {
-            selected.1 = selected.1.saturating_sub(1);
+            selected = move_selection(&given, selected, (0, -1));
             changed = true;
         } else if is_down(key_code) {
-            selected.0 = cmp::min(selected.0 + 1, 8);
+            selected = move_selection(&given, selected, (1, 0));
             changed = true;
         } else if is_right(key_code) {
-            selected.1 = cmp::min(selected.1 + 1, 8);
+            selected = move_selection(&given, selected, (0, 1));
             changed = true;
-        } else if typed_sudoku_value != None && given.get(selected.0, selected.1) == Some(&SudokuValue::Empty) {
-            current.set(selected.0, selected.1, typed_sudoku_value.unwrap());
+        } else if let (Some(typed), Some(&SudokuValue::Empty)) = (typed_sudoku_value, given.get(selected.0, selected.1)) {
+            // values beyond the board size are ignored
+            if typed as usize > side {
+                continue;
+            }
+            current.set(selected.0, selected.1, typed).unwrap();
             wrong_values.remove(&(selected.0, selected.1));
+            conflicts.retain(|&(a, b)| a != selected && b != selected);
             changed = true;
         } else if key_code == KeyCode::Char(CHECK_KEY) {
-            for r in 0..9 {
-                for c in 0..9 {
-                    if current.get(r, c) != Some(&SudokuValue::Empty) && current.get(r, c) != solution.get(r, c) {
-                        if wrong_values.insert((r, c)) {
-                            changed = true;
-                        }
+            let found = current.conflicts();
+            if found != conflicts {
+                conflicts = found;
+                changed = true;
+            }
+            for r in 0..side {
+                for c in 0..side {
+                    if current.get(r, c) != Some(&SudokuValue::Empty) && current.get(r, c) != solution.get(r, c)
+                        && wrong_values.insert((r, c)) {
+                        changed = true;
                     }
                 }
             }
         } else if key_code == KeyCode::Char(HINTS_KEY) {
+            // the next logical placement, starting from the correct values only
+            let mut correct = current.clone();
             let mut empty_values = Vec::new();
-            for r in 0..9 {
-                for c in 0..9 {
-                    if current.get(r, c) == Some(&SudokuValue::Empty) {
+            for r in 0..side {
+                for c in 0..side {
+                    if current.get(r, c) != solution.get(r, c) {
+                        correct.set(r, c, SudokuValue::Empty).unwrap();
                         empty_values.push((r, c));
                     }
                 }
             }
-            if let Some((chosen_r, chosen_c)) = empty_values.choose(&mut rand::thread_rng()) {
-                let right_value = *solution.get(*chosen_r, *chosen_c).unwrap();
-                current.set(*chosen_r, *chosen_c, right_value);
-                selected = (*chosen_r, *chosen_c);
+            let step = correct.solve_logically().into_iter().find(|step| !step.placements.is_empty());
+            let chosen = match &step {
+                Some(step) => step.placements.first().map(|&(r, c, _)| (r, c)),
+                None => empty_values.choose(&mut rand::thread_rng()).copied()
+            };
+            if let Some((chosen_r, chosen_c)) = chosen {
+                let right_value = *solution.get(chosen_r, chosen_c).unwrap();
+                current.set(chosen_r, chosen_c, right_value).unwrap();
+                wrong_values.remove(&(chosen_r, chosen_c));
+                conflicts.retain(|&(a, b)| a != (chosen_r, chosen_c) && b != (chosen_r, chosen_c));
+                selected = (chosen_r, chosen_c);
+                tip = step;
                 changed = true;
             }
         }
@@ -227,28 +460,215 @@ fn game<W: 'static + io::Write + Send>(w: Arc<Mutex<W>>, term: Arc<Mutex<ui::Ter
 
     // end
     execute!(&mut *w.lock().unwrap(), style::ResetColor, crossterm::terminal::Clear(crossterm::terminal::ClearType::All))?;
+    let saved = if win {
+        save::remove()
+    } else {
+        let mut wrong: Vec<(usize, usize)> = wrong_values.into_iter().collect();
+        wrong.sort_unstable();
+        let elapsed = timer.elapsed().as_secs();
+        save::store(&Saved::from(Game { id, given, current, solution, wrong, conflicts, elapsed }))
+    };
+    Ok(saved.err().map(|err| format!("The game couldn't be saved: {}", err)))
+}
+
+fn draw_sudoku_lines<W: io::Write, B: Board>(w: &mut W, term: &ui::Terminal, board: &B, scroll: (i32, i32)) -> crossterm::Result<()>{
+    let layout = board.layout();
+    let (margin, (width, height)) = (clue_margin(layout), (frame_width(layout), frame_height(layout)));
+    // the grid starts right of and below the clues outside of it
+    let (left, top) = (term.h_center() as i32 - width/2 - scroll.0 + margin.0, term.v_center() as i32 - height/2 - scroll.1 + margin.1);
+    for (i, this_bar) in bars(board).iter().enumerate() {
+        let y = top + i as i32;
+        if y < 0 || y >= term.height() as i32 {
+            continue;
+        }
+        // only the part of the line that is on the terminal
+        let visible: String = this_bar.chars().enumerate()
+            .filter(|&(x, _)| (0..term.width() as i32).contains(&(left + x as i32)))
+            .map(|(_, c)| c)
+            .collect();
+        queue!(w, cursor::MoveTo(cmp::max(left, 0) as u16, y as u16), style::Print(visible))?;
+    }
+    draw_constraint_marks(w, term, board, scroll)?;
+    draw_outside_clues(w, term, board, (left - margin.0, top - margin.1), scroll)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Draws the constraints that lie between the cells of a bordered board over its lines:
+/// the lines of thermometers and arrows, the Kropki dots, the greater-than signs and the bars between consecutive digits.
+fn draw_constraint_marks<W: io::Write, B: Board>(w: &mut W, term: &ui::Terminal, board: &B, scroll: (i32, i32)) -> crossterm::Result<()> {
+    let layout = board.layout();
+    let position = |(r, c): (usize, usize)| (term.h_center() as i32 + col_number_offset(layout, c as i32) - scroll.0,
+                                             term.v_center() as i32 + row_number_offset(layout, r as i32) - scroll.1);
+    let path = |cells: &[(usize, usize)]| cells.windows(2).map(|pair| (pair[0], pair[1], line_between(pair[0], pair[1]))).collect_vec();
+    for constraint in board.constraints() {
+        let (marks, color) = match constraint {
+            Constraint::Thermo { cells } => (path(cells), color::BULB),
+            Constraint::Arrow { cells } => (path(cells), color::ARROW),
+            Constraint::Kropki { dot, cells } => (vec![(cells[0], cells[1], match dot { Dot::White => '○', Dot::Black => '●' })], color::DOT),
+            Constraint::Inequality { cells: [smaller, bigger] } => (vec![(*smaller, *bigger, sign_between(*smaller, *bigger))], color::SIGN),
+            // across the line between the cells
+            Constraint::Consecutive { cells: [a, b], bar: true } => (vec![(*a, *b, if a.0 == b.0 { '┃' } else { '━' })], color::BAR),
+            _ => continue
+        };
+        for (a, b, mark) in marks {
+            // the gap between two cells is halfway between them
+            let ((xa, ya), (xb, yb)) = (position(a), position(b));
+            let (x, y) = ((xa + xb) / 2, (ya + yb) / 2);
+            if (0..term.width() as i32).contains(&x) && (0..term.height() as i32).contains(&y) {
+                queue!(w, cursor::MoveTo(x as u16, y as u16), style::SetForegroundColor(color), style::Print(mark), style::ResetColor)?;
+            }
+        }
+    }
     Ok(())
 }
 
-fn draw_sudoku_lines<W: io::Write>(w: &mut W, term: &ui::Terminal) -> crossterm::Result<()>{
-    for i in 0..=12 {
-        let this_bar = match i {
-            0 => top_bar(),
-            12 => bot_bar(),
-            4 | 8 => div_bar(),
-            _ => num_bar()
+/// Draws the clues of the constraints outside of the grid into the margin left of the rows and above the columns,
+/// `origin` is the top left corner of the margin. The digits of a column clue are stacked.
+fn draw_outside_clues<W: io::Write, B: Board>(w: &mut W, term: &ui::Terminal, board: &B, origin: (i32, i32), scroll: (i32, i32)) -> crossterm::Result<()> {
+    let layout = board.layout();
+    let margin = clue_margin(layout);
+    for constraint in board.constraints() {
+        let (cells, sum) = match constraint {
+            Constraint::Sandwich { cells, sum } => (cells, sum),
+            _ => continue
+        };
+        let (r, c) = cells[0];
+        let text = sum.to_string();
+        let positions = if r == cells[1].0 {
+            // right aligned, one column away from the grid
+            let y = term.v_center() as i32 + row_number_offset(layout, r as i32) - scroll.1;
+            let x = origin.0 + margin.0 - 1 - text.len() as i32;
+            text.chars().enumerate().map(|(i, digit)| (x + i as i32, y, digit)).collect_vec()
+        } else {
+            // bottom aligned, right above the grid
+            let x = term.h_center() as i32 + col_number_offset(layout, c as i32) - scroll.0;
+            let y = origin.1 + margin.1 - text.len() as i32;
+            text.chars().enumerate().map(|(i, digit)| (x, y + i as i32, digit)).collect_vec()
         };
-        queue!(w, cursor::MoveTo(term.h_center()-12, term.v_center()-6+i), style::Print(this_bar))?;
+        for (x, y, digit) in positions {
+            if (0..term.width() as i32).contains(&x) && (0..term.height() as i32).contains(&y) {
+                queue!(w, cursor::MoveTo(x as u16, y as u16), style::Print(digit))?;
+            }
+        }
     }
-    w.flush()?;
     Ok(())
 }
 
-fn draw_sudoku_values<W: io::Write>(w: &mut W, term: &ui::Terminal, sud: &Sudoku, given: &Sudoku, selected: (usize, usize), win: bool, wrong_values: &HashSet<(usize, usize)>) -> crossterm::Result<()> {
-    for r in 0_usize..9 {
-        for c in 0_usize..9 {
-            queue!(w, cursor::MoveTo((term.h_center() as i32 + col_number_offset(c as i32)) as u16, (term.v_center() as i32 + row_number_offset(r as i32)) as u16))?;
-            let sud_val = sud.get(r, c).unwrap();
+/// Returns the sign between two neighbours that points at the smaller one, as in `1 < 2`.
+fn sign_between(smaller: (usize, usize), bigger: (usize, usize)) -> char {
+    match (smaller.0 == bigger.0, smaller < bigger) {
+        (true, true)   => '<',
+        (true, false)  => '>',
+        (false, true)  => '^',
+        (false, false) => 'v'
+    }
+}
+
+/// Returns the line that connects two touching cells.
+fn line_between((r1, c1): (usize, usize), (r2, c2): (usize, usize)) -> char {
+    if r1 == r2 {
+        '─'
+    } else if c1 == c2 {
+        '│'
+    } else if (r1 < r2) == (c1 < c2) {
+        '╲'
+    } else {
+        '╱'
+    }
+}
+
+/// Returns how many columns and rows the board is moved left and up, so that the selected cell stays on the terminal.
+/// Boards that fit the terminal aren't moved, the others only as far as needed and never past their edges.
+fn board_scroll(term: &ui::Terminal, layout: Layout, selected: (usize, usize), scroll: (i32, i32)) -> (i32, i32) {
+    // `offset` is the position of the cell relative to the centre of the board, `length` the length of the board
+    let axis = |offset: i32, length: i32, screen: i32, scroll: i32| -> i32 {
+        if length <= screen {
+            return 0;
+        }
+        let position = screen / 2 + offset - scroll;
+        let scroll = if position < SCROLL_MARGIN {
+            scroll + position - SCROLL_MARGIN
+        } else if position >= screen - SCROLL_MARGIN {
+            scroll + position - screen + SCROLL_MARGIN + 1
+        } else {
+            scroll
+        };
+        scroll.clamp(screen / 2 - length / 2, screen / 2 - length / 2 + length - screen)
+    };
+    (axis(col_number_offset(layout, selected.1 as i32), frame_width(layout), term.width() as i32, scroll.0),
+     axis(row_number_offset(layout, selected.0 as i32), frame_height(layout), term.height() as i32, scroll.1))
+}
+
+/// Returns the next cell from the selected one in the direction, skipping the cells that aren't part of the board
+/// like the gaps between the grids of a Samurai. Stays on the selected cell at the edge of the board.
+fn move_selection<B: Board>(board: &B, selected: (usize, usize), (dr, dc): (i32, i32)) -> (usize, usize) {
+    let side = board.layout().side as i32;
+    let (mut r, mut c) = (selected.0 as i32 + dr, selected.1 as i32 + dc);
+    while (0..side).contains(&r) && (0..side).contains(&c) {
+        if board.get(r as usize, c as usize).is_some() {
+            return (r as usize, c as usize);
+        }
+        r += dr;
+        c += dc;
+    }
+    selected
+}
+
+/// Returns the background color of every cell in a cage or in an extra region.
+fn constraint_backgrounds<B: Board>(board: &B) -> HashMap<(usize, usize), style::Color> {
+    let side = board.layout().side;
+    let cages: Vec<Constraint> = board.constraints().iter()
+        .filter(|constraint| matches!(constraint, Constraint::Cage { .. }))
+        .cloned()
+        .collect();
+    let mut backgrounds = HashMap::new();
+    for constraint in board.constraints() {
+        match constraint {
+            Constraint::Region { shape, cells } => {
+                let color = match shape {
+                    Shape::Diagonal => color::DIAGONAL,
+                    Shape::Window   => color::WINDOW
+                };
+                backgrounds.extend(cells.iter().map(|&cell| (cell, color)));
+            },
+            Constraint::Thermo { cells } => {
+                backgrounds.extend(cells.iter().map(|&cell| (cell, color::THERMO)));
+                backgrounds.insert(cells[0], color::BULB);
+            },
+            Constraint::Arrow { cells } => {
+                backgrounds.insert(cells[0], color::ARROW);
+            },
+            Constraint::Parity { parity, cell } => {
+                backgrounds.insert(*cell, match parity {
+                    Parity::Even => color::EVEN,
+                    Parity::Odd  => color::ODD
+                });
+            },
+            _ => {}
+        }
+    }
+    for (cage, color) in cages.iter().zip(cage_colors(&cages, side, color::CAGES.len())) {
+        for &cell in cage.cells() {
+            backgrounds.insert(cell, color::CAGES[color]);
+        }
+    }
+    backgrounds
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_sudoku_values<W: io::Write, B: Board>(w: &mut W, term: &ui::Terminal, sud: &B, given: &B, backgrounds: &HashMap<(usize, usize), style::Color>, selected: (usize, usize), win: bool, wrong_values: &HashSet<(usize, usize)>, scroll: (i32, i32)) -> crossterm::Result<()> {
+    let layout = sud.layout();
+    let side = layout.side;
+    for r in 0..side {
+        for c in 0..side {
+            let (x, y) = (term.h_center() as i32 + col_number_offset(layout, c as i32) - scroll.0, term.v_center() as i32 + row_number_offset(layout, r as i32) - scroll.1);
+            // cells between the grids of a Samurai, or moved off the terminal
+            let sud_val = match sud.get(r, c) {
+                Some(sud_val) if x >= 0 && y >= 0 && x + 1 < term.width() as i32 && y < term.height() as i32 => sud_val,
+                _ => continue
+            };
+            queue!(w, cursor::MoveTo(x as u16, y as u16))?;
             let underline = if !win && selected == (r, c) { Underlined } else { NoUnderline };
             let color = match (given.get(r, c), wrong_values.get(&(r, c))) {
                 (_, Some(_))                    => color::WRONG_NUMBER,
@@ -256,20 +676,83 @@ fn draw_sudoku_values<W: io::Write>(w: &mut W, term: &ui::Terminal, sud: &Sudoku
                 _                               => color::GIVEN_NUMBER
             };
 
+            let background = backgrounds.get(&(r, c)).copied();
+            if let Some(background) = background {
+                queue!(w, style::SetBackgroundColor(background))?;
+            }
             queue!(w, style::SetForegroundColor(color), style::Print(format!("{}{}{}", underline, sud_val, NoUnderline)), style::ResetColor)?;
+            // the gap to the right neighbour takes the color of the cage if both cells are in it and in the same region
+            if let (Some(background), true) = (background, c + 1 < side && sud.region(r, c) == sud.region(r, c + 1)) {
+                let joined = sud.constraints().iter()
+                    .any(|constraint| matches!(constraint, Constraint::Cage { .. }) && constraint.contains(r, c) && constraint.contains(r, c + 1));
+                if joined {
+                    queue!(w, style::SetBackgroundColor(background), style::Print(' '), style::ResetColor)?;
+                }
+            }
         }
     }
     w.flush()?;
     Ok(())
 }
 
-pub fn draw_time<W: io::Write>(w: &mut W, term: &ui::Terminal, seconds: u64) {
-    queue!(w, cursor::MoveTo(term.h_center() + 13, term.v_center()-1), style::Print(time_top_bar())).unwrap();
-    queue!(w, cursor::MoveTo(term.h_center() + 13, term.v_center()),   style::Print(time_bet_bar())).unwrap();
-    queue!(w, cursor::MoveTo(term.h_center() + 13, term.v_center()+1), style::Print(time_bot_bar())).unwrap();
+fn draw_puzzle_info<W: io::Write>(w: &mut W, term: &ui::Terminal, layout: Layout, id: Option<PuzzleId>, rating: &Rating, hints: usize, minimal: bool) -> crossterm::Result<()> {
+    let info = info_column(term, layout);
+    // the difficulty the puzzle is rated, generation falls back to the closest one if the requested one isn't reached
+    let rated = rating.difficulty().map_or("Unrated".to_string(), |difficulty| difficulty.to_string());
+    let difficulty = match id.map(|id| id.settings.difficulty) {
+        Some(requested) if rating.difficulty() != Some(requested) => format!("{} instead of {}", rated, requested),
+        _ => rated
+    };
+    queue!(w, cursor::MoveTo(info, term.v_center()+3), style::Print(difficulty))?;
+    queue!(w, cursor::MoveTo(info, term.v_center()+4), style::Print(rating))?;
+    if let Some(id) = id {
+        queue!(w, cursor::MoveTo(info, term.v_center()+5), style::Print(format!("ID {}", id)))?;
+    }
+    queue!(w, cursor::MoveTo(info, term.v_center()+6), style::Print(format!("{} hints{}", hints, if minimal { ", minimal" } else { "" })))?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Shows the constraints of the selected cell below the puzzle info, like the sum of its cage.
+fn draw_constraint_info<W: io::Write, B: Board>(w: &mut W, term: &ui::Terminal, given: &B, selected: (usize, usize)) -> crossterm::Result<()> {
+    let info = info_column(term, given.layout());
+    let text = given.constraints().iter()
+        .filter(|constraint| constraint.contains(selected.0, selected.1))
+        .map(|constraint| constraint.to_string())
+        // a cell is part of many chess constraints with the same name
+        .unique()
+        .join(", ");
+    queue!(w, cursor::MoveTo(info, term.v_center()+7), crossterm::terminal::Clear(crossterm::terminal::ClearType::UntilNewLine), style::Print(text))?;
+    w.flush()?;
+    Ok(())
+}
+
+fn draw_tip<W: io::Write>(w: &mut W, term: &ui::Terminal, layout: Layout, tip: Option<&Step>) -> crossterm::Result<()> {
+    // below the board
+    let row = (term.v_center() as i32 + frame_height(layout) / 2 + 2) as u16;
+    queue!(w, cursor::MoveTo(0, row), crossterm::terminal::Clear(crossterm::terminal::ClearType::CurrentLine))?;
+    if let Some(step) = tip {
+        let text = format!("Tip: {}", step);
+        queue!(w, cursor::MoveTo(term.h_center_str(&text), row), style::Print(text))?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+pub fn draw_time<W: io::Write>(w: &mut W, term: &ui::Terminal, layout: Layout, seconds: u64) {
+    let info = info_column(term, layout);
+    queue!(w, cursor::MoveTo(info, term.v_center()-1), style::Print(time_top_bar())).unwrap();
+    queue!(w, cursor::MoveTo(info, term.v_center()),   style::Print(time_bet_bar())).unwrap();
+    queue!(w, cursor::MoveTo(info, term.v_center()+1), style::Print(time_bot_bar())).unwrap();
     let mins = seconds / 60;
     let secs = seconds % 60;
     let timer_time = format!("{:02}:{:02}", mins, secs);
-    queue!(w, cursor::MoveTo(term.h_center() + 14, term.v_center()), style::Print(timer_time)).unwrap();
+    queue!(w, cursor::MoveTo(info + 1, term.v_center()), style::Print(timer_time)).unwrap();
     w.flush().unwrap();
 }
+
+/// The column right of the board, where the timer and the puzzle info are drawn.
+/// Boards wider than the terminal get the info drawn over their right side.
+fn info_column(term: &ui::Terminal, layout: Layout) -> u16 {
+    cmp::min((term.h_center() as i32 + frame_width(layout) / 2 + 1) as u16, term.width().saturating_sub(time_top_bar().chars().count() as u16))
+}
@@ -0,0 +1,276 @@
+use rand::Rng;
+use rand::seq::{IteratorRandom, SliceRandom};
+use super::constraint::{Constraint, Dot, Parity};
+use super::sudoku::Sudoku;
+use super::regions::{neighbours, touching};
+
+// number of cells a thermometer grows to, if the digits of its neighbours allow it
+const MIN_THERMO: usize = 3;
+const MAX_THERMO: usize = 6;
+// number of cells along an arrow, without the circle
+const MIN_ARROW: usize = 2;
+const MAX_ARROW: usize = 4;
+
+/// Places thermometers along increasing digits of the solved board, every cell is on at most one of them.
+/// A thermometer starts at a random bulb and steps to orthogonal or diagonal neighbours with bigger digits.
+pub fn thermometers<R: Rng>(solution: &Sudoku, rng: &mut R) -> Vec<Constraint> {
+    let side = solution.size().side();
+    let value = |(r, c): (usize, usize)| *solution.get(r, c).unwrap() as u8;
+    let mut used = vec![vec![false; side]; side];
+    let mut bulbs = cells(side);
+    bulbs.shuffle(rng);
+
+    let mut thermos = Vec::new();
+    for bulb in bulbs.into_iter().take(2 * side) {
+        if thermos.len() == side {
+            break;
+        }
+        if used[bulb.0][bulb.1] {
+            continue;
+        }
+        let target = rng.gen_range(MIN_THERMO..=MAX_THERMO);
+        let mut cells = vec![bulb];
+        while cells.len() < target {
+            let &(r, c) = cells.last().unwrap();
+            let next = touching(r, c, side).into_iter()
+                .filter(|&(nr, nc)| !used[nr][nc] && !cells.contains(&(nr, nc)) && value((nr, nc)) > value((r, c)))
+                .choose(rng);
+            match next {
+                Some(cell) => cells.push(cell),
+                None => break
+            }
+        }
+        if cells.len() >= MIN_THERMO {
+            for &(r, c) in &cells {
+                used[r][c] = true;
+            }
+            thermos.push(Constraint::Thermo { cells });
+        }
+    }
+    thermos.sort_unstable();
+    thermos
+}
+
+/// Places arrows whose digits sum up to the digit of their circle on the solved board, every cell is on at most one of them.
+pub fn arrows<R: Rng>(solution: &Sudoku, rng: &mut R) -> Vec<Constraint> {
+    let side = solution.size().side();
+    let value = |(r, c): (usize, usize)| *solution.get(r, c).unwrap() as u8;
+    let mut used = vec![vec![false; side]; side];
+    let mut circles = cells(side);
+    circles.shuffle(rng);
+
+    let mut arrows = Vec::new();
+    for circle in circles {
+        if arrows.len() == side {
+            break;
+        }
+        // the smallest arrow needs a sum of at least 1 + 1
+        if used[circle.0][circle.1] || value(circle) < MIN_ARROW as u8 {
+            continue;
+        }
+        let mut cells = vec![circle];
+        if extend_arrow(&mut cells, value(circle), &value, &used, side, rng) {
+            for &(r, c) in &cells {
+                used[r][c] = true;
+            }
+            arrows.push(Constraint::Arrow { cells });
+        }
+    }
+    arrows.sort_unstable();
+    arrows
+}
+
+/// Extends the arrow with touching cells until their digits add up to `remaining`.
+/// Returns `false` and leaves the arrow as it was if no path of the allowed length fits.
+fn extend_arrow<R: Rng, F: Fn((usize, usize)) -> u8>(cells: &mut Vec<(usize, usize)>, remaining: u8, value: &F,
+                                                      used: &[Vec<bool>], side: usize, rng: &mut R) -> bool {
+    // the circle is not part of the length
+    let len = cells.len() - 1;
+    if remaining == 0 {
+        return len >= MIN_ARROW;
+    }
+    if len == MAX_ARROW {
+        return false;
+    }
+    let &(r, c) = cells.last().unwrap();
+    let mut next: Vec<(usize, usize)> = touching(r, c, side).into_iter()
+        .filter(|&(nr, nc)| !used[nr][nc] && !cells.contains(&(nr, nc)) && value((nr, nc)) <= remaining)
+        .collect();
+    next.shuffle(rng);
+    for cell in next {
+        cells.push(cell);
+        if extend_arrow(cells, remaining - value(cell), value, used, side, rng) {
+            return true;
+        }
+        cells.pop();
+    }
+    false
+}
+
+/// Places a Kropki dot between every two orthogonal neighbours of the solved board whose digits are
+/// consecutive or where one is twice the other. `1` and `2` fit both dots and get a white one.
+pub fn dots(solution: &Sudoku) -> Vec<Constraint> {
+    let value = |(r, c): (usize, usize)| *solution.get(r, c).unwrap() as u8;
+    pairs(solution.size().side()).into_iter()
+        .filter_map(|cells| {
+            let (a, b) = (value(cells[0]), value(cells[1]));
+            if a.abs_diff(b) == 1 {
+                Some(Constraint::Kropki { dot: Dot::White, cells })
+            } else if a == 2 * b || b == 2 * a {
+                Some(Constraint::Kropki { dot: Dot::Black, cells })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Places a greater-than sign between every two orthogonal neighbours of the solved board,
+/// the smaller digit comes first. Most of them are removed again, see [Sudoku::thin_clues].
+pub fn inequalities(solution: &Sudoku) -> Vec<Constraint> {
+    let value = |(r, c): (usize, usize)| *solution.get(r, c).unwrap() as u8;
+    pairs(solution.size().side()).into_iter()
+        .map(|[a, b]| Constraint::Inequality { cells: if value(a) < value(b) { [a, b] } else { [b, a] } })
+        .collect()
+}
+
+/// Places a [Constraint::Consecutive] clue between every two orthogonal neighbours of the solved board,
+/// with a bar where their digits are consecutive and without one everywhere else.
+pub fn bars(solution: &Sudoku) -> Vec<Constraint> {
+    let value = |(r, c): (usize, usize)| *solution.get(r, c).unwrap() as u8;
+    pairs(solution.size().side()).into_iter()
+        .map(|cells| Constraint::Consecutive { cells, bar: value(cells[0]).abs_diff(value(cells[1])) == 1 })
+        .collect()
+}
+
+/// Marks every cell of the solved board as even or odd. Most of them are removed again, see [Sudoku::thin_clues].
+pub fn parities(solution: &Sudoku) -> Vec<Constraint> {
+    cells(solution.size().side()).into_iter()
+        .map(|(r, c)| {
+            let parity = if (*solution.get(r, c).unwrap() as u8).is_multiple_of(2) { Parity::Even } else { Parity::Odd };
+            Constraint::Parity { parity, cell: (r, c) }
+        })
+        .collect()
+}
+
+/// Returns every two orthogonal neighbours of a board, the first cell comes first in row-major order.
+pub fn pairs(side: usize) -> Vec<[(usize, usize); 2]> {
+    cells(side).into_iter()
+        .flat_map(|(r, c)| neighbours(r, c, side).into_iter().filter(move |&other| other > (r, c)).map(move |other| [(r, c), other]))
+        .collect()
+}
+
+fn cells(side: usize) -> Vec<(usize, usize)> {
+    (0..side).flat_map(|r| (0..side).map(move |c| (r, c))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::settings::Settings;
+    use crate::size::Size;
+    use crate::solver::Uniqueness;
+    use crate::variant::Variant;
+
+    /// Asserts that a generated puzzle of the variant is unique and its clues allow the solution.
+    fn assert_generates_unique_puzzles(variant: Variant, size: Size) {
+        let settings = Settings { variant, size, difficulty: Difficulty::Easy, ..Settings::default() };
+        let (puzzle, solution) = Sudoku::new(&settings, 5).unwrap();
+        assert_eq!(puzzle.uniqueness(), Uniqueness::Unique, "{}", variant);
+        // the solution keeps every clue, the puzzle only the ones it needs
+        assert_eq!(puzzle.solution().unwrap().to_cells(), solution.to_cells(), "{}", variant);
+        assert!(solution.solved(), "{}", variant);
+        for constraint in puzzle.constraints() {
+            assert!(constraint.allows(size.side(), |r, c| *solution.get(r, c).unwrap() as u8), "{}: {}", variant, constraint);
+        }
+    }
+
+    /// Returns a filled board of the size.
+    fn solution(size: Size, seed: u64) -> Sudoku {
+        let mut solution = Sudoku::empty(size);
+        solution.fill(&mut ChaCha8Rng::seed_from_u64(seed)).unwrap();
+        solution
+    }
+
+    /// Returns whether the constraint allows the solution with `cell` changed to `digit`.
+    fn allows_with(constraint: &Constraint, solution: &Sudoku, cell: (usize, usize), digit: u8) -> bool {
+        constraint.allows(solution.size().side(), |r, c| if (r, c) == cell { digit } else { *solution.get(r, c).unwrap() as u8 })
+    }
+
+    #[test]
+    fn thermometers_allow_the_solution() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for size in [Size::SIX, Size::NINE] {
+            let solution = solution(size, 1);
+            let thermos = thermometers(&solution, &mut rng);
+            assert!(!thermos.is_empty(), "{}", size);
+            for thermo in &thermos {
+                let cells = thermo.cells();
+                assert!(cells.len() >= MIN_THERMO && cells.len() <= MAX_THERMO);
+                assert!(thermo.allows(size.side(), |r, c| *solution.get(r, c).unwrap() as u8));
+                // the digit of the bulb in the last cell is smaller than the one before it
+                let bulb = *solution.get(cells[0].0, cells[0].1).unwrap() as u8;
+                assert!(!allows_with(thermo, &solution, cells[cells.len() - 1], bulb), "{:?}", thermo);
+            }
+        }
+    }
+
+    #[test]
+    fn arrows_allow_the_solution() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        for size in [Size::SIX, Size::NINE] {
+            let solution = solution(size, 2);
+            let arrows = arrows(&solution, &mut rng);
+            assert!(!arrows.is_empty(), "{}", size);
+            for arrow in &arrows {
+                let cells = arrow.cells();
+                assert!(cells.len() > MIN_ARROW && cells.len() <= MAX_ARROW + 1);
+                assert!(arrow.allows(size.side(), |r, c| *solution.get(r, c).unwrap() as u8));
+                let circle = *solution.get(cells[0].0, cells[0].1).unwrap() as u8;
+                let other = if circle == size.side() as u8 { circle - 1 } else { circle + 1 };
+                assert!(!allows_with(arrow, &solution, cells[0], other), "{:?}", arrow);
+            }
+        }
+    }
+
+    #[test]
+    fn dots_allow_the_solution() {
+        let solution = solution(Size::NINE, 3);
+        let dots = dots(&solution);
+        assert!(dots.iter().any(|dot| matches!(dot, Constraint::Kropki { dot: Dot::White, .. })));
+        assert!(dots.iter().any(|dot| matches!(dot, Constraint::Kropki { dot: Dot::Black, .. })));
+        for kropki in &dots {
+            assert!(kropki.allows(9, |r, c| *solution.get(r, c).unwrap() as u8));
+            // no dot fits between a 1 and a 5
+            let Constraint::Kropki { cells: [a, b], .. } = *kropki else { panic!("{:?} is not a Kropki dot", kropki) };
+            let broken = |r, c| if (r, c) == a { 1 } else if (r, c) == b { 5 } else { 0 };
+            assert!(!kropki.allows(9, broken), "{:?}", kropki);
+        }
+        // every pair without a dot has neither consecutive digits nor one twice the other
+        for [a, b] in pairs(9) {
+            if !dots.iter().any(|dot| dot.cells() == [a, b]) {
+                let (x, y) = (*solution.get(a.0, a.1).unwrap() as u8, *solution.get(b.0, b.1).unwrap() as u8);
+                assert!(x.abs_diff(y) != 1 && x != 2 * y && y != 2 * x);
+            }
+        }
+    }
+
+    #[test]
+    fn generates_unique_greater_than_puzzles() {
+        assert_generates_unique_puzzles(Variant::GreaterThan, Size::SIX);
+    }
+
+    #[test]
+    fn generates_unique_consecutive_puzzles() {
+        assert_generates_unique_puzzles(Variant::Consecutive, Size::SIX);
+        assert_generates_unique_puzzles(Variant::NonConsecutive, Size::SIX);
+    }
+
+    #[test]
+    fn generates_unique_even_odd_puzzles() {
+        assert_generates_unique_puzzles(Variant::EvenOdd, Size::SIX);
+    }
+}
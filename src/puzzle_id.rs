@@ -0,0 +1,213 @@
+use std::convert::TryFrom;
+use std::fmt::Formatter;
+use super::difficulty::Difficulty;
+use super::settings::Settings;
+use super::size::Size;
+use super::sudoku::Sudoku;
+use super::samurai::Samurai;
+use super::symmetry::Symmetry;
+use super::variant::Variant;
+use super::chess::Piece;
+use super::error::SudokuError;
+
+// Crockford's base32, without the easily confused I, L, O and U
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+// marks minimal puzzles, not used by any Symmetry::code
+const MINIMAL_CODE: char = 'M';
+
+/// Identifies a generated puzzle by its settings and seed, so it can be shared and played again.
+/// Written as the first letter of the difficulty, the side length of the board, the [Symmetry::code],
+/// an `M` for minimal puzzles, the [Piece::code] of the chess rules and the seed in base32, for example `M16RM-3F9K2A`.
+/// The side length is left out for 9x9 boards and the symmetry code for [Symmetry::None], as in `M-3F9K2A`.
+/// Variants other than [Variant::Classic] put their [Variant::code] in front, as in `K-M-3F9K2A`.
+/// Serialized as this string.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct PuzzleId {
+    pub settings: Settings,
+    pub seed: u32,
+}
+
+impl PuzzleId {
+    /// Creates an id with a random seed.
+    pub fn random(settings: Settings) -> Self {
+        PuzzleId { settings, seed: rand::random() }
+    }
+
+    /// Generates the puzzle of this id, use [PuzzleId::generate_samurai] for [Variant::Samurai].
+    /// Returns `(unsolved sudoku, solved sudoku)`.
+    pub fn generate(&self) -> Result<(Sudoku, Sudoku), SudokuError> {
+        Sudoku::new(&self.settings, self.seed as u64)
+    }
+
+    /// Generates the Samurai of this id.
+    /// Returns `(unsolved samurai, solved samurai)`.
+    pub fn generate_samurai(&self) -> Result<(Samurai, Samurai), SudokuError> {
+        Samurai::new(&self.settings, self.seed as u64)
+    }
+}
+
+impl std::fmt::Display for PuzzleId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut seed = self.seed;
+        let mut digits = Vec::new();
+        loop {
+            digits.push(ALPHABET[(seed % 32) as usize] as char);
+            seed /= 32;
+            if seed == 0 {
+                break;
+            }
+        }
+        if self.settings.variant != Variant::Classic {
+            write!(f, "{}-", self.settings.variant.code())?;
+        }
+        write!(f, "{}", self.settings.difficulty.to_string().chars().next().unwrap())?;
+        if self.settings.size != Size::NINE {
+            write!(f, "{}", self.settings.size.side())?;
+        }
+        if self.settings.symmetry != Symmetry::None {
+            write!(f, "{}", self.settings.symmetry.code())?;
+        }
+        if self.settings.minimal {
+            write!(f, "{}", MINIMAL_CODE)?;
+        }
+        for piece in self.settings.pieces() {
+            write!(f, "{}", piece.code())?;
+        }
+        write!(f, "-{}", digits.iter().rev().collect::<String>())
+    }
+}
+
+impl TryFrom<&str> for PuzzleId {
+    type Error = SudokuError;
+
+    /// Parses ids like `M16RM-3F9K2A`, `M-3F9K2A` or `K-M-3F9K2A`, case insensitive.
+    fn try_from(id: &str) -> Result<Self, Self::Error> {
+        let id = id.trim().to_ascii_uppercase();
+        let parts: Vec<&str> = id.split('-').collect();
+        let (variant, codes, seed) = match parts[..] {
+            [codes, seed] => (Variant::Classic, codes, seed),
+            [variant, codes, seed] => match Variant::from_code(variant) {
+                Some(variant) if variant != Variant::Classic => (variant, codes, seed),
+                _ => return Err(SudokuError::Parse(format!("Unknown variant {} in the puzzle ID {}", variant, id)))
+            },
+            [_] => return Err(SudokuError::Parse(format!("The puzzle ID {} is missing the '-' after the difficulty", id))),
+            _ => return Err(SudokuError::Parse(format!("The puzzle ID {} has too many '-'", id)))
+        };
+        let mut codes = codes.chars();
+        let difficulty = match codes.next() {
+            Some('E') => Difficulty::Easy,
+            Some('M') => Difficulty::Medium,
+            Some('H') => Difficulty::Hard,
+            _ => return Err(SudokuError::Parse(format!("Unknown difficulty in the puzzle ID {}, it has to start with E, M or H", id)))
+        };
+        let side: String = codes.clone().take_while(|c| c.is_ascii_digit()).collect();
+        let size = if side.is_empty() {
+            Size::NINE
+        } else {
+            match side.parse().ok().and_then(Size::from_side) {
+                Some(size) if size != Size::NINE => size,
+                _ => return Err(SudokuError::Parse(format!("Unsupported board size {} in the puzzle ID {}", side, id)))
+            }
+        };
+        let mut settings = Settings { size, variant, difficulty, ..Settings::default() };
+        for code in codes.skip(side.len()) {
+            if let Some(piece) = Piece::from_code(code).filter(|&piece| !settings.has(piece)) {
+                settings.set(piece, true);
+            } else if code == MINIMAL_CODE && !settings.minimal {
+                settings.minimal = true;
+            } else if let (Some(symmetry), Symmetry::None, false) = (Symmetry::from_code(code), settings.symmetry, settings.minimal) {
+                settings.symmetry = symmetry;
+            } else {
+                return Err(SudokuError::Parse(format!("Unexpected letter {} before the '-' in the puzzle ID {}", code, id)));
+            }
+        }
+        if settings.minimal && !settings.size.supports_minimal() {
+            return Err(SudokuError::Parse(format!("Minimal puzzles are not supported for {} boards", settings.size)));
+        }
+        if settings.minimal && !settings.supports_minimal() {
+            return Err(SudokuError::Parse(format!("Minimal puzzles are not supported for {} Sudoku", settings.variant)));
+        }
+        if !settings.variant.supports(settings.size) {
+            return Err(SudokuError::Parse(format!("{} Sudoku is not supported for {} boards", settings.variant, settings.size)));
+        }
+        if let Some(piece) = settings.pieces().first().filter(|_| !Piece::supports(settings.size, settings.variant)) {
+            return Err(SudokuError::Parse(format!("{} is not supported for {} Sudoku on {} boards", piece, settings.variant, settings.size)));
+        }
+        if seed.is_empty() || seed.len() > 7 {
+            return Err(SudokuError::Parse(format!("The seed of the puzzle ID has to have 1 to 7 characters, it has {}", seed.len())));
+        }
+        let mut value: u64 = 0;
+        for c in seed.bytes() {
+            match ALPHABET.iter().position(|&a| a == c) {
+                Some(digit) => value = value * 32 + digit as u64,
+                None => return Err(SudokuError::Parse(format!("Invalid character {} in the puzzle ID", c as char)))
+            }
+        }
+        match u32::try_from(value) {
+            Ok(seed) => Ok(PuzzleId { settings, seed }),
+            Err(_) => Err(SudokuError::Parse(format!("The seed {} of the puzzle ID is too large", seed)))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PuzzleId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PuzzleId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        PuzzleId::try_from(id.as_str()).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_what_it_displays() {
+        let settings = [
+            Settings::default(),
+            Settings { size: Size::SIX, symmetry: Symmetry::Rotational180, minimal: true, ..Settings::default() },
+            Settings { variant: Variant::Killer, difficulty: Difficulty::Hard, anti_king: true, ..Settings::default() },
+            Settings { size: Size::SIX, variant: Variant::EvenOdd, difficulty: Difficulty::Easy, symmetry: Symmetry::Diagonal, ..Settings::default() },
+            Settings { size: Size::EIGHT, symmetry: Symmetry::Rotational90, anti_knight: true, anti_king: true, ..Settings::default() }
+        ];
+        for settings in settings {
+            for seed in [0, 31, 32, 123_456_789, u32::MAX] {
+                let id = PuzzleId { settings, seed };
+                assert_eq!(PuzzleId::try_from(id.to_string().as_str()).unwrap(), id, "{}", id);
+                assert_eq!(PuzzleId::try_from(id.to_string().to_lowercase().as_str()).unwrap(), id, "{}", id);
+            }
+        }
+        assert_eq!(PuzzleId { settings: settings[1], seed: 3_287_066 }.to_string(), "M6RM-34A0T");
+        assert_eq!(PuzzleId { settings: settings[2], seed: 32 }.to_string(), "K-HK-10");
+    }
+
+    #[test]
+    fn generates_the_same_puzzle_from_the_parsed_id() {
+        let settings = Settings { size: Size::SIX, difficulty: Difficulty::Easy, symmetry: Symmetry::Rotational180, ..Settings::default() };
+        let id = PuzzleId { settings, seed: 42 };
+        let parsed = PuzzleId::try_from(id.to_string().as_str()).unwrap();
+        assert_eq!(parsed.generate().unwrap(), id.generate().unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_ids() {
+        for id in [
+            "", "M", "M-", "M-12345678", "M-1-2-3",
+            // I, L, O and U aren't base32 digits, and 32^7 doesn't fit the seed
+            "M-1I", "M-UL", "M-ZZZZZZZ",
+            "M7-1", "M9-1", "M100-1",
+            "Z-M-1", "-M-1", "M16M-1", "X-MJ-1", "T-MM-1",
+            "Q-1", "-1", "MZ-1", "MRR-1", "MMM-1", "MJJ-1"
+        ] {
+            assert!(matches!(PuzzleId::try_from(id), Err(SudokuError::Parse(_))), "{}", id);
+        }
+    }
+}
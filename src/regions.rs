@@ -0,0 +1,201 @@
+use rand::Rng;
+use rand::seq::IteratorRandom;
+use super::size::Size;
+use super::sudoku::Sudoku;
+#[cfg(feature = "serde")]
+use super::format;
+
+// number of tried swaps per cell, every one that keeps the regions connected changes the layout
+const SWAPS_PER_CELL: usize = 20;
+
+/// Splits a board into `side` regions of `side` cells that can't repeat a digit,
+/// the squares of a classic Sudoku or the irregular shapes of a Jigsaw Sudoku.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Hash)]
+pub struct Regions {
+    side: usize,
+    // region of every cell, row-major
+    map: Vec<usize>,
+}
+
+impl Regions {
+    /// Returns the squares of the size, counted row-major like [Size::square].
+    pub fn squares(size: Size) -> Self {
+        let side = size.side();
+        Regions { side, map: (0..size.cells()).map(|i| size.square(i / side, i % side)).collect() }
+    }
+
+    /// Creates irregular, orthogonally connected regions that the solved board still satisfies.
+    /// Starts with the squares and swaps cells with the same digit between touching regions,
+    /// so every region keeps every digit. Swaps that put more cells back into their squares are skipped.
+    pub fn random<R: Rng>(solution: &Sudoku, rng: &mut R) -> Self {
+        let size = solution.size();
+        let side = size.side();
+        let mut regions = Regions::squares(size);
+        let value = |i: usize| *solution.get(i / side, i % side).unwrap();
+        for _ in 0..SWAPS_PER_CELL * size.cells() {
+            let a = rng.gen_range(0..size.cells());
+            let other = regions.touching(a).choose(rng);
+            let b = match other {
+                // the cell of the other region with the same digit, it has to touch the region of a
+                Some(other) => (0..size.cells()).find(|&b| regions.map[b] == other && value(b) == value(a)).unwrap(),
+                None => continue
+            };
+            if !regions.touching(b).any(|region| region == regions.map[a]) {
+                continue;
+            }
+            let (region_a, region_b) = (regions.map[a], regions.map[b]);
+            // swaps that move cells back into their squares undo earlier swaps, the regions would stay close to the squares
+            let square = |i: usize| size.square(i / side, i % side);
+            let in_square_after = [(a, region_b), (b, region_a)].iter().filter(|&&(i, region)| region == square(i)).count();
+            let in_square_before = [(a, region_a), (b, region_b)].iter().filter(|&&(i, region)| region == square(i)).count();
+            if in_square_after > in_square_before {
+                continue;
+            }
+            regions.map[a] = region_b;
+            regions.map[b] = region_a;
+            if !regions.is_connected(region_a) || !regions.is_connected(region_b) {
+                regions.map[a] = region_a;
+                regions.map[b] = region_b;
+            }
+        }
+        regions
+    }
+
+    /// Returns the index of the region the cell lies in.
+    pub fn region(&self, r: usize, c: usize) -> usize {
+        self.map[r * self.side + c]
+    }
+
+    /// Returns the cells of the region with the index `s`, row-major.
+    pub fn cells(&self, s: usize) -> Vec<(usize, usize)> {
+        (0..self.map.len()).filter(|&i| self.map[i] == s).map(|i| (i / self.side, i % self.side)).collect()
+    }
+
+    /// Returns `true` if the regions are the squares of the size.
+    pub fn is_squares(&self, size: Size) -> bool {
+        (0..self.map.len()).all(|i| self.map[i] == size.square(i / self.side, i % self.side))
+    }
+
+    /// Returns the other regions the cell touches.
+    fn touching(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let region = self.map[i];
+        neighbours(i / self.side, i % self.side, self.side).into_iter()
+            .map(move |(r, c)| self.map[r * self.side + c])
+            .filter(move |&other| other != region)
+    }
+
+    fn is_connected(&self, s: usize) -> bool {
+        let cells = self.cells(s);
+        let mut reached = vec![cells[0]];
+        let mut k = 0;
+        while k < reached.len() {
+            let (r, c) = reached[k];
+            for cell in neighbours(r, c, self.side) {
+                if self.region(cell.0, cell.1) == s && !reached.contains(&cell) {
+                    reached.push(cell);
+                }
+            }
+            k += 1;
+        }
+        reached.len() == cells.len()
+    }
+}
+
+/// Serialized as a string with the region of every cell, row-major, counted from `1` and written like the values,
+/// so the squares of a 4x4 board are `"1122112233443344"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Regions {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.map.iter().map(|&s| format::to_char(s as u8 + 1)).collect::<String>())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Regions {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let text = String::deserialize(deserializer)?;
+        let len = text.chars().count();
+        let size = Size::all().into_iter().find(|size| size.cells() == len)
+            .ok_or_else(|| D::Error::custom(format!("{} regions don't fit any board", len)))?;
+        let side = size.side();
+        let map = text.chars()
+            .map(|c| format::from_char(c, side).filter(|&s| s > 0).map(|s| s as usize - 1)
+                .ok_or_else(|| D::Error::custom(format!("{} is not a region of a {} board", c, size))))
+            .collect::<Result<Vec<usize>, D::Error>>()?;
+        if let Some(s) = (0..side).find(|&s| map.iter().filter(|&&other| other == s).count() != side) {
+            return Err(D::Error::custom(format!("Region {} doesn't have {} cells", format::to_char(s as u8 + 1), side)));
+        }
+        let regions = Regions { side, map };
+        if let Some(s) = (0..side).find(|&s| !regions.is_connected(s)) {
+            return Err(D::Error::custom(format!("Region {} isn't connected", format::to_char(s as u8 + 1))));
+        }
+        Ok(regions)
+    }
+}
+
+/// Returns the orthogonal neighbours of a cell.
+pub(crate) fn neighbours(r: usize, c: usize, side: usize) -> Vec<(usize, usize)> {
+    let mut neighbours = Vec::with_capacity(4);
+    if r > 0 {
+        neighbours.push((r - 1, c));
+    }
+    if r + 1 < side {
+        neighbours.push((r + 1, c));
+    }
+    if c > 0 {
+        neighbours.push((r, c - 1));
+    }
+    if c + 1 < side {
+        neighbours.push((r, c + 1));
+    }
+    neighbours
+}
+
+/// Returns the orthogonal and diagonal neighbours of a cell.
+pub(crate) fn touching(r: usize, c: usize, side: usize) -> Vec<(usize, usize)> {
+    (r.saturating_sub(1)..(r + 2).min(side))
+        .flat_map(|nr| (c.saturating_sub(1)..(c + 2).min(side)).map(move |nc| (nr, nc)))
+        .filter(|&cell| cell != (r, c))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use itertools::Itertools;
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::settings::Settings;
+    use crate::solver::Uniqueness;
+    use crate::variant::Variant;
+
+    #[test]
+    fn random_regions_are_connected_and_keep_the_solution() {
+        let mut rng = ChaCha8Rng::seed_from_u64(6);
+        for size in [Size::FOUR, Size::SIX, Size::NINE, Size::TWELVE] {
+            let mut solution = Sudoku::empty(size);
+            solution.fill(&mut rng).unwrap();
+            let regions = Regions::random(&solution, &mut rng);
+            assert!(!regions.is_squares(size), "{}", size);
+            for s in 0..size.side() {
+                let cells = regions.cells(s);
+                assert_eq!(cells.len(), size.side(), "{}", size);
+                assert!(regions.is_connected(s), "{}", size);
+                assert!(cells.iter().map(|&(r, c)| solution.get(r, c).unwrap()).all_unique(), "{}", size);
+            }
+        }
+    }
+
+    #[test]
+    fn generates_unique_jigsaw_puzzles() {
+        let settings = Settings { variant: Variant::Jigsaw, size: Size::NINE, difficulty: Difficulty::Easy, ..Settings::default() };
+        let (puzzle, solution) = Sudoku::new(&settings, 2).unwrap();
+        assert!(!puzzle.regions().is_squares(Size::NINE));
+        assert_eq!(puzzle.regions(), solution.regions());
+        assert_eq!(puzzle.uniqueness(), Uniqueness::Unique);
+        assert!(solution.solved());
+        assert_eq!(puzzle.solution(), Some(solution));
+    }
+}
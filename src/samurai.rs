@@ -0,0 +1,371 @@
+use rand::{Rng, RngCore, SeedableRng};
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use itertools::Itertools;
+use super::sudoku::Sudoku;
+use super::solver::{BitSolver, Uniqueness};
+use super::difficulty::{self, Rating};
+use super::logic::{LogicalSolver, Step};
+use super::settings::Settings;
+use super::size::Size;
+use super::symmetry::Symmetry;
+use super::value::SudokuValue;
+use super::board::Layout;
+use super::error::SudokuError;
+#[cfg(feature = "serde")]
+use super::format;
+
+/// Number of rows and columns of the board the five grids are placed on.
+pub const SIDE: usize = 21;
+/// Top left cells of the grids: the four corners and the centre, which shares a square with every corner grid.
+pub const OFFSETS: [(usize, usize); 5] = [(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)];
+// the grids only come in one size
+const SIZE: Size = Size::NINE;
+// generation gives up on the requested difficulty after this many puzzles
+const MAX_ATTEMPTS: usize = 100;
+
+/// Five overlapping 9x9 Sudokus on a 21x21 board, also known as Gattai-5.
+/// Every shared cell has the same value in the corner grid and in the centre grid.
+/// Cells are `(row, col)` indices of the whole board, the cells between the grids aren't part of it.
+/// Serialized as `{"cells": "..."}` with the values of the whole board row by row, blanks and the cells between the grids are `.`.
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(into = "SamuraiData", try_from = "SamuraiData"))]
+pub struct Samurai {
+    grids: Vec<Sudoku>,
+}
+
+impl Samurai {
+    /// Creates a board without any values.
+    pub fn empty() -> Self {
+        Samurai { grids: vec![Sudoku::empty(SIZE); OFFSETS.len()] }
+    }
+
+    /// Generates puzzles until one is rated with the requested difficulty, like [Sudoku::new].
+    /// Only the difficulty, the symmetry and the minimal setting are used, the grids are always classic 9x9 grids.
+    /// Returns `(unsolved samurai, solved samurai)`, or an error if no Samurai could be generated in any attempt.
+    pub fn new(settings: &Settings, seed: u64) -> Result<(Self, Self), SudokuError> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let hints = settings.difficulty.hints(SIZE) * Samurai::cells().len() / SIZE.cells();
+        let mut closest: Option<(usize, Samurai, Samurai)> = None;
+        for _ in 0..MAX_ATTEMPTS {
+            let mut samurai = Samurai::empty();
+            samurai.fill(&mut rng)?;
+            let solution = samurai.clone();
+            let mut left = samurai.remove_hints(if settings.minimal { 0 } else { hints }, settings.symmetry, &mut rng);
+            // like Sudoku::minimize, the single hints of the orbits that can still be removed are removed as well
+            if settings.minimal && settings.symmetry != Symmetry::None {
+                left = samurai.remove_hints(0, Symmetry::None, &mut rng);
+            }
+            if !settings.minimal && left > hints {
+                continue;
+            }
+            let rated = samurai.rating().difficulty();
+            if rated == Some(settings.difficulty) {
+                return Ok((samurai, solution));
+            }
+            let distance = difficulty::distance(rated, settings.difficulty);
+            if closest.as_ref().is_none_or(|(closest, _, _)| distance < *closest) {
+                closest = Some((distance, samurai, solution));
+            }
+        }
+        match closest {
+            Some((_, samurai, solution)) => Ok((samurai, solution)),
+            None => Err(SudokuError::Generation(format!("No Samurai could be generated in {} attempts", MAX_ATTEMPTS)))
+        }
+    }
+
+    /// Returns the grids the cell is part of, with the index of the grid and the cell inside of it.
+    fn grids_at(r: usize, c: usize) -> impl Iterator<Item = (usize, usize, usize)> {
+        OFFSETS.iter().enumerate()
+            .filter(move |(_, &(top, left))| (top..top + SIZE.side()).contains(&r) && (left..left + SIZE.side()).contains(&c))
+            .map(move |(g, &(top, left))| (g, r - top, c - left))
+    }
+
+    /// Returns `true` if the cell is part of a grid.
+    pub fn contains(r: usize, c: usize) -> bool {
+        Samurai::grids_at(r, c).next().is_some()
+    }
+
+    /// Returns every cell of the board, row-major.
+    pub fn cells() -> Vec<(usize, usize)> {
+        (0..SIDE).cartesian_product(0..SIDE).filter(|&(r, c)| Samurai::contains(r, c)).collect()
+    }
+
+    /// Returns the SudokuValue at the specified indices, `None` between the grids.
+    pub fn get(&self, r: usize, c: usize) -> Option<&SudokuValue> {
+        let (g, r, c) = Samurai::grids_at(r, c).next()?;
+        self.grids[g].get(r, c)
+    }
+
+    /// Sets the value at the specified indices in every grid that shares the cell,
+    /// returns an error for cells that aren't part of a grid.
+    pub fn set(&mut self, r: usize, c: usize, val: SudokuValue) -> Result<(), SudokuError> {
+        if !Samurai::contains(r, c) {
+            return Err(SudokuError::InvalidIndex { r, c, side: SIDE });
+        }
+        for (g, r, c) in Samurai::grids_at(r, c) {
+            self.grids[g].set(r, c, val)?;
+        }
+        Ok(())
+    }
+
+    /// Returns how often the SudokuValue is on the board, shared cells count once.
+    pub fn count(&self, val: SudokuValue) -> usize {
+        Samurai::cells().into_iter().filter(|&(r, c)| self.get(r, c) == Some(&val)).count()
+    }
+
+    /// Returns the number of fields that have a value.
+    pub fn hints(&self) -> usize {
+        Samurai::cells().len() - self.count(SudokuValue::Empty)
+    }
+
+    /// Returns `true` if every grid is completely solved.
+    pub fn solved(&self) -> bool {
+        self.grids.iter().all(|grid| grid.solved())
+    }
+
+    /// Returns the conflicts of every grid, see [Sudoku::conflicts], in board indices.
+    pub fn conflicts(&self) -> Vec<((usize, usize), (usize, usize))> {
+        self.grids.iter().zip(OFFSETS.iter())
+            .flat_map(|(grid, &(top, left))| grid.conflicts().into_iter()
+                .map(move |((r1, c1), (r2, c2))| ((r1 + top, c1 + left), (r2 + top, c2 + left))))
+            .unique()
+            .collect()
+    }
+
+    /// Returns the units of every grid, see [Sudoku::units], in board indices.
+    /// The shared squares are units of two grids, so they appear twice.
+    pub fn units(&self) -> Vec<Vec<(usize, usize)>> {
+        self.grids.iter().zip(OFFSETS.iter())
+            .flat_map(|(grid, &(top, left))| grid.units().into_iter()
+                .map(move |unit| unit.into_iter().map(|(r, c)| (r + top, c + left)).collect()))
+            .collect()
+    }
+
+    /// Returns the board as row-major digits, `0` is an empty field or a cell between the grids.
+    pub fn to_cells(&self) -> Vec<u8> {
+        (0..SIDE).cartesian_product(0..SIDE).map(|(r, c)| self.get(r, c).map_or(0, |&val| val as u8)).collect()
+    }
+
+    /// Replaces the values of the board with row-major digits, inverse of [Samurai::to_cells].
+    /// The digits of the cells between the grids are ignored.
+    /// Returns an error and leaves the board as it was if there isn't a digit for every cell or a digit is bigger than 9.
+    pub fn set_cells(&mut self, cells: &[u8]) -> Result<(), SudokuError> {
+        if cells.len() != SIDE * SIDE {
+            return Err(SudokuError::Parse(format!("{} digits don't fit a Samurai with {} cells", cells.len(), SIDE * SIDE)));
+        }
+        if let Some(&digit) = cells.iter().find(|&&digit| digit as usize > SIZE.side()) {
+            return Err(SudokuError::Parse(format!("{} is not a value of a Samurai", digit)));
+        }
+        for (r, c) in Samurai::cells() {
+            self.set(r, c, SudokuValue::try_from(cells[r * SIDE + c] as i32)?)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of different solutions of the whole board,
+    /// but stops searching as soon as `limit` solutions are found.
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        match BitSolver::with_units(SIZE.side(), SIDE, &self.units(), &[], &self.to_cells()) {
+            Some(mut solver) => solver.count(limit),
+            None => 0
+        }
+    }
+
+    /// Returns whether the board has no, exactly one or multiple solutions.
+    pub fn uniqueness(&self) -> Uniqueness {
+        match self.count_solutions(2) {
+            0 => Uniqueness::None,
+            1 => Uniqueness::Unique,
+            _ => Uniqueness::Multiple
+        }
+    }
+
+    /// Fills the empty fields of every grid with random numbers, so that the board is solved.
+    pub fn fill<R: RngCore>(&mut self, rng: &mut R) -> Result<(), SudokuError> {
+        let units = self.units();
+        let unsolvable = || SudokuError::Generation("The Samurai has no solution to fill it with".to_string());
+        let mut solver = BitSolver::with_units(SIZE.side(), SIDE, &units, &[], &self.to_cells()).ok_or_else(unsolvable)?;
+        if !solver.fill(rng) {
+            return Err(unsolvable());
+        }
+        let cells = solver.solution().ok_or_else(unsolvable)?.to_vec();
+        self.set_cells(&cells)
+    }
+
+    /// Removes the orbits of the symmetry in random order until at most `n` hints are left,
+    /// skipping every orbit that would allow a second solution, like [Sudoku::minimize] does for a single grid.
+    /// The board is symmetric, so every orbit lies either inside the grids or between them.
+    /// Returns the number of hints left.
+    fn remove_hints<R: Rng>(&mut self, n: usize, symmetry: Symmetry, rng: &mut R) -> usize {
+        let mut orbits = symmetry.orbits(SIDE);
+        orbits.retain(|orbit| Samurai::contains(orbit[0].0, orbit[0].1));
+        orbits.shuffle(rng);
+        for orbit in &orbits {
+            if self.hints() <= n {
+                break;
+            }
+            let removed = orbit.iter().map(|&(r, c)| (r, c, *self.get(r, c).unwrap())).collect_vec();
+            for &(r, c) in orbit {
+                self.set(r, c, SudokuValue::Empty).unwrap();
+            }
+            if self.uniqueness() != Uniqueness::Unique {
+                for (r, c, val) in removed {
+                    self.set(r, c, val).unwrap();
+                }
+            }
+        }
+        self.hints()
+    }
+
+    /// Returns `true` if the board has a unique solution and none of its hints can be removed.
+    pub fn is_minimal(&self) -> bool {
+        if self.uniqueness() != Uniqueness::Unique {
+            return false;
+        }
+        let mut samurai = self.clone();
+        Samurai::cells().into_iter().all(|(r, c)| {
+            let val = *self.get(r, c).unwrap();
+            if val == SudokuValue::Empty {
+                return true;
+            }
+            samurai.set(r, c, SudokuValue::Empty).unwrap();
+            let needed = samurai.uniqueness() != Uniqueness::Unique;
+            samurai.set(r, c, val).unwrap();
+            needed
+        })
+    }
+
+    /// Solves the grids with the [LogicalSolver] one after another, passing the digits of the shared squares on,
+    /// until no grid places another digit. Returns the steps in board indices.
+    pub fn solve_logically(&self) -> Vec<Step> {
+        let mut samurai = self.clone();
+        let mut steps = Vec::new();
+        loop {
+            let before = steps.len();
+            for (g, &(top, left)) in OFFSETS.iter().enumerate() {
+                let mut grid_steps = LogicalSolver::new(&samurai.grids[g]).solve();
+                // the steps after the last placement would be found again in the next round
+                let placing = grid_steps.iter().rposition(|step| !step.placements.is_empty()).map_or(0, |last| last + 1);
+                grid_steps.truncate(placing);
+                for mut step in grid_steps {
+                    step.cells.iter_mut().for_each(|(r, c)| { *r += top; *c += left; });
+                    step.placements.iter_mut().for_each(|(r, c, _)| { *r += top; *c += left; });
+                    step.eliminations.iter_mut().for_each(|(r, c, _)| { *r += top; *c += left; });
+                    for &(r, c, val) in &step.placements {
+                        samurai.set(r, c, val).unwrap();
+                    }
+                    steps.push(step);
+                }
+            }
+            if steps.len() == before {
+                break;
+            }
+        }
+        steps
+    }
+
+    /// Rates the board by the steps of [Samurai::solve_logically], like [Rating::new] rates a single grid.
+    pub fn rating(&self) -> Rating {
+        let steps = self.solve_logically();
+        let mut samurai = self.clone();
+        for &(r, c, val) in steps.iter().flat_map(|step| &step.placements) {
+            samurai.set(r, c, val).unwrap();
+        }
+        Rating::from_steps(&steps, samurai.solved())
+    }
+
+    /// Returns where the board is drawn, the squares are the ones of a 9x9 grid.
+    pub fn layout(&self) -> Layout {
+        Layout { size: SIZE, side: SIDE, bordered: false, clues: false }
+    }
+}
+
+/// How a [Samurai] is serialized.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SamuraiData {
+    cells: String,
+}
+
+#[cfg(feature = "serde")]
+impl From<Samurai> for SamuraiData {
+    fn from(samurai: Samurai) -> Self {
+        SamuraiData { cells: samurai.to_cells().into_iter().map(format::to_char).collect() }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SamuraiData> for Samurai {
+    type Error = SudokuError;
+
+    fn try_from(data: SamuraiData) -> Result<Self, Self::Error> {
+        let len = data.cells.chars().count();
+        if len != SIDE * SIDE {
+            return Err(SudokuError::Parse(format!("A Samurai has {} cells, found {}", SIDE * SIDE, len)));
+        }
+        let cells = data.cells.chars()
+            .map(|c| format::from_char(c, SIZE.side()).ok_or_else(|| SudokuError::Parse(format!("{} is not a value of a Samurai", c))))
+            .collect::<Result<Vec<u8>, SudokuError>>()?;
+        // the gaps between the grids are written as empty cells
+        if let Some(i) = (0..cells.len()).find(|&i| cells[i] != 0 && !Samurai::contains(i / SIDE, i % SIDE)) {
+            return Err(SudokuError::Parse(format!("r{}c{} lies between the grids of the Samurai and can't have a value", i / SIDE + 1, i % SIDE + 1)));
+        }
+        let mut samurai = Samurai::empty();
+        samurai.set_cells(&cells)?;
+        Ok(samurai)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Difficulty;
+
+    /// Asserts that every shared cell has the same value in both of its grids.
+    fn assert_overlaps_agree(samurai: &Samurai) {
+        for (r, c) in Samurai::cells() {
+            let values = Samurai::grids_at(r, c).map(|(g, r, c)| *samurai.grids[g].get(r, c).unwrap()).collect_vec();
+            assert!(values.iter().all_equal(), "r{}c{}: {:?}", r + 1, c + 1, values);
+        }
+    }
+
+    #[test]
+    fn fills_the_shared_squares_once() {
+        let mut samurai = Samurai::empty();
+        samurai.fill(&mut ChaCha8Rng::seed_from_u64(8)).unwrap();
+        assert!(samurai.solved());
+        assert!(samurai.conflicts().is_empty());
+        assert_overlaps_agree(&samurai);
+        // the centre grid shares a corner square with every other grid
+        assert_eq!(Samurai::cells().len(), 5 * 81 - 4 * 9);
+        assert_eq!(Samurai::grids_at(7, 7).map(|(g, _, _)| g).collect_vec(), vec![0, 2]);
+    }
+
+    #[test]
+    fn rejects_cells_that_dont_fit() {
+        let mut samurai = Samurai::empty();
+        assert!(samurai.set_cells(&[0; SIDE * SIDE - 1]).is_err());
+        assert!(samurai.set_cells(&[10; SIDE * SIDE]).is_err());
+        assert_eq!(samurai, Samurai::empty());
+        // the cells between the grids are ignored
+        let mut cells = vec![0; SIDE * SIDE];
+        cells[9] = 5;
+        samurai.set_cells(&cells).unwrap();
+        assert_eq!(samurai, Samurai::empty());
+    }
+
+    #[test]
+    fn generates_unique_puzzles() {
+        let settings = Settings { difficulty: Difficulty::Easy, ..Settings::default() };
+        let (puzzle, solution) = Samurai::new(&settings, 4).unwrap();
+        assert_eq!(puzzle.uniqueness(), Uniqueness::Unique);
+        assert!(solution.solved());
+        assert_overlaps_agree(&puzzle);
+        assert_overlaps_agree(&solution);
+        for (r, c) in Samurai::cells() {
+            let given = puzzle.get(r, c).unwrap();
+            assert!(*given == SudokuValue::Empty || given == solution.get(r, c).unwrap());
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use super::constraint::Constraint;
+use super::sudoku::Sudoku;
+
+/// Returns a sandwich clue for every row and column of the solved board,
+/// the sum of the digits between its 1 and its biggest digit.
+pub fn clues(solution: &Sudoku) -> Vec<Constraint> {
+    let side = solution.size().side();
+    let rows = (0..side).map(|r| (0..side).map(|c| (r, c)).collect::<Vec<_>>());
+    let cols = (0..side).map(|c| (0..side).map(|r| (r, c)).collect::<Vec<_>>());
+    rows.chain(cols).map(|cells| {
+        let digits: Vec<u32> = cells.iter().map(|&(r, c)| *solution.get(r, c).unwrap() as u32).collect();
+        let one = digits.iter().position(|&digit| digit == 1).unwrap();
+        let biggest = digits.iter().position(|&digit| digit == side as u32).unwrap();
+        let sum = digits[one.min(biggest) + 1..one.max(biggest)].iter().sum();
+        Constraint::Sandwich { cells, sum }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use super::*;
+    use crate::size::Size;
+
+    #[test]
+    fn sums_the_digits_between_one_and_the_biggest_digit() {
+        let mut solution = Sudoku::empty(Size::FOUR);
+        solution.set_cells(&[
+            1, 2, 3, 4,
+            3, 4, 1, 2,
+            2, 1, 4, 3,
+            4, 3, 2, 1
+        ]).unwrap();
+        let sums: Vec<u32> = clues(&solution).iter()
+            .map(|clue| match clue {
+                Constraint::Sandwich { sum, .. } => *sum,
+                _ => panic!("{:?} is not a sandwich clue", clue)
+            })
+            .collect();
+        assert_eq!(sums, [5, 0, 0, 5, 5, 0, 0, 5]);
+    }
+
+    #[test]
+    fn clues_allow_the_solution() {
+        let mut solution = Sudoku::empty(Size::NINE);
+        solution.fill(&mut ChaCha8Rng::seed_from_u64(7)).unwrap();
+        let clues = clues(&solution);
+        assert_eq!(clues.len(), 18);
+        for clue in &clues {
+            assert!(clue.allows(9, |r, c| *solution.get(r, c).unwrap() as u8), "{}", clue);
+            let Constraint::Sandwich { cells, sum } = clue else { panic!("{:?} is not a sandwich clue", clue) };
+            let broken = Constraint::Sandwich { cells: cells.clone(), sum: sum + 1 };
+            assert!(!broken.allows(9, |r, c| *solution.get(r, c).unwrap() as u8), "{}", broken);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::io;
+use sudoku_game::Game;
+use sudoku_game::sudoku::Sudoku;
+use sudoku_game::samurai::Samurai;
+
+/// The unfinished game left with Escape, continued from the main menu.
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub enum Saved {
+    Sudoku(Box<Game<Sudoku>>),
+    Samurai(Box<Game<Samurai>>)
+}
+
+impl From<Game<Sudoku>> for Saved {
+    fn from(game: Game<Sudoku>) -> Self {
+        Saved::Sudoku(Box::new(game))
+    }
+}
+
+impl From<Game<Samurai>> for Saved {
+    fn from(game: Game<Samurai>) -> Self {
+        Saved::Samurai(Box::new(game))
+    }
+}
+
+#[cfg(feature = "save")]
+fn path() -> io::Result<std::path::PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("sudoku-game").join("saved.json"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "there is no user data directory"))
+}
+
+/// Returns the saved game, `None` if there isn't one.
+#[cfg(feature = "save")]
+pub fn load() -> io::Result<Option<Saved>> {
+    load_from(&path()?)
+}
+
+/// Replaces the saved game.
+#[cfg(feature = "save")]
+pub fn store(saved: &Saved) -> io::Result<()> {
+    store_at(&path()?, saved)
+}
+
+/// Removes the saved game once it's solved.
+#[cfg(feature = "save")]
+pub fn remove() -> io::Result<()> {
+    remove_at(&path()?)
+}
+
+#[cfg(feature = "save")]
+fn load_from(path: &std::path::Path) -> io::Result<Option<Saved>> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err)
+    }
+}
+
+#[cfg(feature = "save")]
+fn store_at(path: &std::path::Path, saved: &Saved) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string(saved)?)
+}
+
+#[cfg(feature = "save")]
+fn remove_at(path: &std::path::Path) -> io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(())
+    }
+}
+
+// without the feature nothing is saved and there's never a game to continue
+#[cfg(not(feature = "save"))]
+pub fn load() -> io::Result<Option<Saved>> {
+    Ok(None)
+}
+
+#[cfg(not(feature = "save"))]
+pub fn store(_saved: &Saved) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(feature = "save"))]
+pub fn remove() -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, feature = "save"))]
+mod tests {
+    use std::path::PathBuf;
+    use sudoku_game::settings::Settings;
+    use sudoku_game::size::Size;
+    use sudoku_game::board::Board;
+    use sudoku_game::difficulty::Difficulty;
+    use sudoku_game::puzzle_id::PuzzleId;
+    use sudoku_game::variant::Variant;
+    use sudoku_game::SudokuValue;
+    use super::*;
+
+    /// Returns a file in a directory of its own that doesn't exist yet, removed again by the test.
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sudoku-game-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("saved.json")
+    }
+
+    /// Returns a game with a value entered into its first empty cell and some time played.
+    fn started<B: Board>(given: B, solution: B) -> Game<B> {
+        let side = given.layout().side;
+        let (r, c) = (0..side).flat_map(|r| (0..side).map(move |c| (r, c)))
+            .find(|&(r, c)| given.get(r, c) == Some(&SudokuValue::Empty)).unwrap();
+        let mut game = Game::new(None, given, solution);
+        game.current.set(r, c, *game.solution.get(r, c).unwrap()).unwrap();
+        game.elapsed = 75;
+        game
+    }
+
+    #[test]
+    fn stores_loads_and_removes_games() {
+        let path = temp_path("round-trip");
+        assert!(load_from(&path).unwrap().is_none());
+        // removing a game that isn't there isn't an error
+        remove_at(&path).unwrap();
+
+        let id = PuzzleId { settings: Settings { size: Size::SIX, difficulty: Difficulty::Easy, ..Settings::default() }, seed: 3 };
+        let (given, solution) = id.generate().unwrap();
+        let game = Game { id: Some(id), ..started(given, solution) };
+        store_at(&path, &Saved::from(game.clone())).unwrap();
+        match load_from(&path).unwrap() {
+            Some(Saved::Sudoku(loaded)) => assert_eq!(*loaded, game),
+            _ => panic!("the saved Sudoku wasn't loaded")
+        }
+        remove_at(&path).unwrap();
+        assert!(load_from(&path).unwrap().is_none());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn resumes_samurai_games() {
+        let path = temp_path("resume");
+        let id = PuzzleId { settings: Settings { variant: Variant::Samurai, difficulty: Difficulty::Easy, ..Settings::default() }, seed: 4 };
+        let (given, solution) = id.generate_samurai().unwrap();
+        let game = started(given, solution);
+        store_at(&path, &Saved::from(game.clone())).unwrap();
+        // a newer save replaces the older one
+        let played = Game { elapsed: 120, ..game };
+        store_at(&path, &Saved::from(played.clone())).unwrap();
+        match load_from(&path).unwrap() {
+            Some(Saved::Samurai(loaded)) => {
+                assert_eq!(loaded.current.hints(), loaded.given.hints() + 1);
+                assert_eq!(*loaded, played);
+            },
+            _ => panic!("the saved Samurai wasn't loaded")
+        }
+        // a save that doesn't belong to any game is an error instead of a game
+        std::fs::write(&path, r#"{"Sudoku":{"id":null}}"#).unwrap();
+        assert!(load_from(&path).is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,64 @@
+use super::difficulty::Difficulty;
+use super::symmetry::Symmetry;
+use super::size::Size;
+use super::variant::Variant;
+use super::chess::Piece;
+
+/// Everything the player chooses in the menu before a puzzle is generated.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Settings {
+    pub size: Size,
+    pub variant: Variant,
+    pub difficulty: Difficulty,
+    pub symmetry: Symmetry,
+    /// Remove as many hints as possible instead of stopping at the hint count of the difficulty.
+    pub minimal: bool,
+    /// Cells a knight's move apart can't have the same digit.
+    pub anti_knight: bool,
+    /// Cells a king's move apart can't have the same digit.
+    pub anti_king: bool,
+}
+
+impl Settings {
+    /// Returns the pieces whose moves can't repeat a digit, on top of the rules of the variant.
+    pub fn pieces(&self) -> Vec<Piece> {
+        Piece::all().iter().copied().filter(|&piece| self.has(piece)).collect()
+    }
+
+    pub fn has(&self, piece: Piece) -> bool {
+        match piece {
+            Piece::Knight => self.anti_knight,
+            Piece::King   => self.anti_king
+        }
+    }
+
+    pub fn set(&mut self, piece: Piece, on: bool) {
+        match piece {
+            Piece::Knight => self.anti_knight = on,
+            Piece::King   => self.anti_king = on
+        }
+    }
+
+    /// Minimal puzzles need a board that supports them, see [Size::supports_minimal], and a variant whose hints
+    /// aren't reduced, see [Variant::is_reduced], as their constraints make proving that every hint is needed too slow.
+    pub fn supports_minimal(&self) -> bool {
+        self.size.supports_minimal() && !self.variant.is_reduced()
+    }
+
+    /// Turns off the pieces if they don't support the size and the variant, see [Piece::supports].
+    pub fn remove_unsupported_pieces(&mut self) {
+        if !Piece::supports(self.size, self.variant) {
+            self.anti_knight = false;
+            self.anti_king = false;
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            size: Size::default(), variant: Variant::Classic, difficulty: Difficulty::Medium, symmetry: Symmetry::None, minimal: false,
+            anti_knight: false, anti_king: false
+        }
+    }
+}
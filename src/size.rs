@@ -0,0 +1,83 @@
+use std::fmt::Formatter;
+
+/// Dimensions of a board. A board has `box_rows * box_cols` rows, columns and squares,
+/// and every square is `box_rows` high and `box_cols` wide.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug, Hash)]
+pub struct Size {
+    pub box_rows: usize,
+    pub box_cols: usize,
+}
+
+impl Size {
+    pub const FOUR: Size        = Size { box_rows: 2, box_cols: 2 };
+    pub const SIX: Size         = Size { box_rows: 2, box_cols: 3 };
+    pub const EIGHT: Size       = Size { box_rows: 2, box_cols: 4 };
+    pub const NINE: Size        = Size { box_rows: 3, box_cols: 3 };
+    pub const TWELVE: Size      = Size { box_rows: 3, box_cols: 4 };
+    pub const SIXTEEN: Size     = Size { box_rows: 4, box_cols: 4 };
+    pub const TWENTY_FIVE: Size = Size { box_rows: 5, box_cols: 5 };
+
+    /// Returns every supported size, from the smallest to the largest.
+    pub fn all() -> [Size; 7] {
+        [Size::FOUR, Size::SIX, Size::EIGHT, Size::NINE, Size::TWELVE, Size::SIXTEEN, Size::TWENTY_FIVE]
+    }
+
+    /// Returns the supported size with `side` rows.
+    pub fn from_side(side: usize) -> Option<Size> {
+        Size::all().iter().copied().find(|size| size.side() == side)
+    }
+
+    /// Number of rows and columns, which is also the largest value on the board.
+    pub fn side(self) -> usize {
+        self.box_rows * self.box_cols
+    }
+
+    /// Number of cells on the board.
+    pub fn cells(self) -> usize {
+        self.side() * self.side()
+    }
+
+    /// Minimal puzzles of bigger boards have too few hints to check their uniqueness in reasonable time.
+    pub fn supports_minimal(self) -> bool {
+        self.side() <= 12
+    }
+
+    /// Returns the index of the square the cell lies in, squares are counted row-major.
+    pub fn square(self, r: usize, c: usize) -> usize {
+        (r / self.box_rows) * self.box_rows + c / self.box_cols
+    }
+
+    /// Returns the cells of the square with the index `s`.
+    pub fn square_cells(self, s: usize) -> Vec<(usize, usize)> {
+        let top = (s / self.box_rows) * self.box_rows;
+        let left = (s % self.box_rows) * self.box_cols;
+        let mut cells = Vec::with_capacity(self.side());
+        for r in top..top + self.box_rows {
+            for c in left..left + self.box_cols {
+                cells.push((r, c));
+            }
+        }
+        cells
+    }
+
+    pub fn next(self) -> Self {
+        let all = Size::all();
+        all[(self.index() + 1) % all.len()]
+    }
+
+    fn index(self) -> usize {
+        Size::all().iter().position(|&size| size == self).unwrap()
+    }
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Size::NINE
+    }
+}
+
+impl std::fmt::Display for Size {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.side(), self.side())
+    }
+}
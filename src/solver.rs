@@ -0,0 +1,417 @@
+use rand::RngCore;
+use rand::seq::SliceRandom;
+use super::sudoku::Sudoku;
+use super::constraint::Constraint;
+use super::error::SudokuError;
+
+// a random fill starts over with a new order once it searched this many nodes per cell,
+// every restart raises the limit by an eighth so the search still ends on boards without a solution
+const FILL_NODES_PER_CELL: usize = 10;
+
+/// How many solutions a Sudoku has.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Uniqueness {
+    None,
+    Unique,
+    Multiple
+}
+
+/// A backend that solves, counts and fills Sudokus.
+pub trait Solver {
+    /// Returns a solution of the Sudoku, `None` if it has none.
+    fn solve(&self, sudoku: &Sudoku) -> Option<Sudoku>;
+
+    /// Returns the number of solutions, but stops searching once `limit` solutions are found.
+    fn count(&self, sudoku: &Sudoku, limit: usize) -> usize;
+
+    /// Fills the empty fields with random values, so that the Sudoku is solved.
+    /// Returns an error and leaves the Sudoku as it was if it has no solution.
+    fn fill(&self, sudoku: &mut Sudoku, rng: &mut dyn RngCore) -> Result<(), SudokuError>;
+}
+
+/// [Solver] backed by the [BitSolver].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Backtracking;
+
+impl Solver for Backtracking {
+    fn solve(&self, sudoku: &Sudoku) -> Option<Sudoku> {
+        let mut solver = BitSolver::new(sudoku)?;
+        solver.count(1);
+        let mut solution = sudoku.clone();
+        solution.set_cells(solver.solution()?).ok()?;
+        Some(solution)
+    }
+
+    fn count(&self, sudoku: &Sudoku, limit: usize) -> usize {
+        match BitSolver::new(sudoku) {
+            Some(mut solver) => solver.count(limit),
+            None => 0
+        }
+    }
+
+    fn fill(&self, sudoku: &mut Sudoku, rng: &mut dyn RngCore) -> Result<(), SudokuError> {
+        let unsolvable = || SudokuError::Generation("The Sudoku has no solution to fill it with".to_string());
+        let mut solver = BitSolver::new(sudoku).ok_or_else(unsolvable)?;
+        if !solver.fill(rng) {
+            return Err(unsolvable());
+        }
+        let cells = solver.solution().ok_or_else(unsolvable)?.to_vec();
+        sudoku.set_cells(&cells)
+    }
+}
+
+/// Backtracking solver that keeps a bitmask of the used digits of every unit,
+/// the rows, columns, regions and the cells of a constraint that can't repeat a digit.
+/// Bit `d - 1` of a mask is set when the digit `d` is already placed in that unit.
+/// Cells are stored row-major with `0` for an empty cell.
+/// Cells that aren't part of any unit, like the gaps between the grids of a Samurai, are never filled.
+#[derive(Clone)]
+pub struct BitSolver<'a> {
+    // number of digits, the side of a single grid
+    side: usize,
+    // number of columns of the board, used to find the cells of the constraints
+    width: usize,
+    // mask with a bit for every digit of the board
+    all: u32,
+    cells: Vec<u8>,
+    // cell indices of every unit
+    units: Vec<Vec<usize>>,
+    // the units every cell is part of
+    cell_units: Vec<Vec<usize>>,
+    masks: Vec<u32>,
+    constraints: &'a [Constraint],
+    // the constraints every cell is part of
+    cell_constraints: Vec<Vec<usize>>,
+    // indices of every cell placed by the search, used to undo a branch
+    trail: Vec<usize>,
+    solution: Option<Vec<u8>>,
+    // number of searched nodes, the search stops once it exceeds the limit
+    nodes: usize,
+    max_nodes: usize,
+}
+
+impl<'a> BitSolver<'a> {
+    /// Creates a solver for the board and the constraints of the Sudoku.
+    /// Returns `None` if a digit appears twice in a unit.
+    pub fn new(sudoku: &'a Sudoku) -> Option<Self> {
+        let n = sudoku.size().side();
+        BitSolver::with_units(n, n, &sudoku.units(), sudoku.constraints(), &sudoku.to_cells())
+    }
+
+    /// Creates a solver for a board with `width` columns and the digits from 1 to `side`,
+    /// like the composite board of a [Samurai](super::samurai::Samurai).
+    /// `values` are the row-major digits of the board, `0` is an empty cell.
+    /// Returns `None` if a digit appears twice in a unit.
+    pub fn with_units(side: usize, width: usize, units: &[Vec<(usize, usize)>], constraints: &'a [Constraint], values: &[u8]) -> Option<Self> {
+        let units: Vec<Vec<usize>> = units.iter()
+            .map(|unit| unit.iter().map(|&(r, c)| r * width + c).collect())
+            .collect();
+        let mut cell_units = vec![Vec::new(); values.len()];
+        for (u, unit) in units.iter().enumerate() {
+            for &i in unit {
+                cell_units[i].push(u);
+            }
+        }
+        let mut cell_constraints = vec![Vec::new(); values.len()];
+        for (k, constraint) in constraints.iter().enumerate() {
+            for &(r, c) in constraint.cells() {
+                cell_constraints[r * width + c].push(k);
+            }
+        }
+        let mut solver = BitSolver {
+            side,
+            width,
+            all: (1 << side) - 1,
+            cells: vec![0; values.len()],
+            masks: vec![0; units.len()],
+            units,
+            cell_units,
+            constraints,
+            cell_constraints,
+            trail: Vec::with_capacity(values.len()),
+            solution: None,
+            nodes: 0,
+            max_nodes: usize::MAX,
+        };
+        for (i, &digit) in values.iter().enumerate() {
+            if digit == 0 {
+                continue;
+            }
+            let bit = 1 << (digit - 1);
+            if solver.used(i) & bit != 0 {
+                return None;
+            }
+            solver.place(i, digit);
+        }
+        solver.trail.clear();
+        Some(solver)
+    }
+
+    /// Returns the number of solutions, but stops searching once `limit` solutions are found.
+    pub fn count(&mut self, limit: usize) -> usize {
+        let mut found = 0;
+        self.search(limit, &mut found, None);
+        found
+    }
+
+    /// Completes the board with random digits.
+    /// Returns `false` if the board has no solution.
+    pub fn fill(&mut self, rng: &mut dyn RngCore) -> bool {
+        self.max_nodes = FILL_NODES_PER_CELL * self.cells.len();
+        loop {
+            let mut found = 0;
+            self.nodes = 0;
+            self.search(1, &mut found, Some(rng));
+            if found == 1 {
+                return true;
+            }
+            if self.nodes <= self.max_nodes {
+                return false;
+            }
+            // some random orders run into dead ends that take very long to leave, a new order is faster
+            self.max_nodes += self.max_nodes / 8;
+        }
+    }
+
+    /// Returns the first solution found by the last [BitSolver::count] or [BitSolver::fill].
+    pub fn solution(&self) -> Option<&[u8]> {
+        self.solution.as_deref()
+    }
+
+    fn search(&mut self, limit: usize, found: &mut usize, mut rng: Option<&mut dyn RngCore>) {
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            return;
+        }
+        let mark = self.trail.len();
+        match self.propagate() {
+            // contradiction, some cell has no candidates left
+            Err(()) => {},
+            // every cell is filled, the givens aren't checked against the constraints before
+            Ok(None) => {
+                if self.satisfies_constraints() {
+                    *found += 1;
+                    if self.solution.is_none() {
+                        self.solution = Some(self.cells.clone());
+                    }
+                }
+            },
+            Ok(Some((i, mask))) => {
+                let mut digits: Vec<u8> = (1..=self.side as u8).filter(|d| mask & (1 << (d - 1)) != 0).collect();
+                if let Some(rng) = rng.as_mut() {
+                    digits.shuffle(rng);
+                }
+                for digit in digits {
+                    self.place(i, digit);
+                    self.search(limit, found, rng.as_mut().map(|rng| &mut **rng as &mut dyn RngCore));
+                    self.remove(i);
+                    self.trail.pop();
+                    if *found >= limit {
+                        break;
+                    }
+                }
+            }
+        }
+        self.undo(mark);
+    }
+
+    /// Places every naked and hidden single until none are left.
+    /// Returns the empty cell with the fewest candidates and its candidates,
+    /// `None` if the board is full or `Err` if a cell or a digit of a unit has no candidates.
+    fn propagate(&mut self) -> Result<Option<(usize, u32)>, ()> {
+        loop {
+            let mut best: Option<(usize, u32)> = None;
+            let mut best_count = u32::MAX;
+            let mut placed = false;
+            for i in 0..self.cells.len() {
+                if self.cells[i] != 0 || self.cell_units[i].is_empty() {
+                    continue;
+                }
+                let mask = self.candidates(i);
+                match mask.count_ones() {
+                    0 => return Err(()),
+                    1 => {
+                        self.place(i, mask.trailing_zeros() as u8 + 1);
+                        placed = true;
+                    },
+                    n if n < best_count => {
+                        best = Some((i, mask));
+                        best_count = n;
+                    },
+                    _ => {}
+                }
+            }
+            if !placed && best.is_some() {
+                placed = self.hidden_singles()?;
+            }
+            if !placed {
+                return Ok(best);
+            }
+        }
+    }
+
+    /// Places every digit that fits in only one cell of a unit that has to contain every digit.
+    /// Returns whether anything was placed, or `Err` if a digit fits nowhere in a unit.
+    fn hidden_singles(&mut self) -> Result<bool, ()> {
+        let n = self.side;
+        let mut placed = false;
+        for u in 0..self.units.len() {
+            if self.units[u].len() != n {
+                continue;
+            }
+            let (mut once, mut twice) = (0, 0);
+            for k in 0..n {
+                let i = self.units[u][k];
+                if self.cells[i] == 0 {
+                    let mask = self.candidates(i);
+                    twice |= once & mask;
+                    once |= mask;
+                } else {
+                    once |= 1 << (self.cells[i] - 1);
+                }
+            }
+            if once != self.all {
+                return Err(());
+            }
+            // digits that are already placed are in `once` as well
+            let mut hidden = once & !twice & !self.masks[u];
+            while hidden != 0 {
+                let bit = hidden & hidden.wrapping_neg();
+                hidden &= !bit;
+                let mut cell = None;
+                for k in 0..n {
+                    let i = self.units[u][k];
+                    if self.cells[i] == 0 && self.candidates(i) & bit != 0 {
+                        cell = Some(i);
+                        break;
+                    }
+                }
+                match cell {
+                    Some(i) => self.place(i, bit.trailing_zeros() as u8 + 1),
+                    // an earlier hidden single of this unit took the only cell
+                    None => return Err(())
+                }
+                placed = true;
+            }
+        }
+        Ok(placed)
+    }
+
+    /// Returns the digits that don't repeat a digit of a unit and that the constraints of the cell allow.
+    fn candidates(&mut self, i: usize) -> u32 {
+        let mut mask = !self.used(i) & self.all;
+        if self.cell_constraints[i].is_empty() {
+            return mask;
+        }
+        let mut rest = mask;
+        while rest != 0 {
+            let bit = rest & rest.wrapping_neg();
+            rest &= !bit;
+            self.cells[i] = bit.trailing_zeros() as u8 + 1;
+            if !self.cell_constraints[i].iter().all(|&k| self.allows(k)) {
+                mask &= !bit;
+            }
+        }
+        self.cells[i] = 0;
+        mask
+    }
+
+    fn allows(&self, k: usize) -> bool {
+        self.constraints[k].allows(self.side, |r, c| self.cells[r * self.width + c])
+    }
+
+    fn satisfies_constraints(&self) -> bool {
+        (0..self.constraints.len()).all(|k| self.allows(k))
+    }
+
+    fn used(&self, i: usize) -> u32 {
+        self.cell_units[i].iter().fold(0, |used, &u| used | self.masks[u])
+    }
+
+    fn place(&mut self, i: usize, digit: u8) {
+        let bit = 1 << (digit - 1);
+        self.cells[i] = digit;
+        for &u in &self.cell_units[i] {
+            self.masks[u] |= bit;
+        }
+        self.trail.push(i);
+    }
+
+    fn remove(&mut self, i: usize) {
+        let bit = !(1 << (self.cells[i] - 1));
+        self.cells[i] = 0;
+        for &u in &self.cell_units[i] {
+            self.masks[u] &= bit;
+        }
+    }
+
+    fn undo(&mut self, mark: usize) {
+        while self.trail.len() > mark {
+            let i = self.trail.pop().unwrap();
+            self.remove(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use super::*;
+    use crate::size::Size;
+
+    const PUZZLE: &str = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+    const SOLUTION: &str = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+    #[test]
+    fn solves_a_known_puzzle() {
+        let sudoku = Sudoku::try_from(PUZZLE).unwrap();
+        let solution = Sudoku::try_from(SOLUTION).unwrap();
+        assert_eq!(Backtracking.solve(&sudoku), Some(solution.clone()));
+        let mut solver = BitSolver::new(&sudoku).unwrap();
+        assert_eq!(solver.count(2), 1);
+        assert_eq!(solver.solution(), Some(&solution.to_cells()[..]));
+    }
+
+    #[test]
+    fn rejects_duplicate_givens() {
+        let sudoku = Sudoku::empty(Size::NINE);
+        let mut values = vec![0; 81];
+        // two 5s in the first column
+        values[0] = 5;
+        values[4 * 9] = 5;
+        assert!(BitSolver::with_units(9, 9, &sudoku.units(), &[], &values).is_none());
+        values[4 * 9] = 6;
+        assert!(BitSolver::with_units(9, 9, &sudoku.units(), &[], &values).is_some());
+    }
+
+    #[test]
+    fn fills_empty_boards_of_every_size() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for size in Size::all() {
+            let mut sudoku = Sudoku::empty(size);
+            let mut solver = BitSolver::new(&sudoku).unwrap();
+            assert!(solver.fill(&mut rng), "{}", size);
+            let cells = solver.solution().unwrap().to_vec();
+            sudoku.set_cells(&cells).unwrap();
+            assert!(sudoku.solved(), "{}", size);
+        }
+    }
+
+    #[test]
+    fn does_not_fill_contradictions() {
+        // the first row needs a 9 in its last cell, but the last column already has one
+        let mut cells = vec![0; 81];
+        cells[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        cells[9 + 8] = 9;
+        let mut sudoku = Sudoku::empty(Size::NINE);
+        sudoku.set_cells(&cells).unwrap();
+        let mut solver = BitSolver::new(&sudoku).unwrap();
+        assert!(!solver.fill(&mut ChaCha8Rng::seed_from_u64(3)));
+        assert_eq!(solver.solution(), None);
+        let before = sudoku.clone();
+        assert!(Backtracking.fill(&mut sudoku, &mut ChaCha8Rng::seed_from_u64(3)).is_err());
+        assert_eq!(sudoku, before);
+    }
+}
@@ -1,177 +1,313 @@
-#[cfg(test)]
-
-
-use rand;
+use rand::{Rng, RngCore, SeedableRng};
 use rand::seq::SliceRandom;
-use itertools;
+use rand_chacha::ChaCha8Rng;
 use itertools::Itertools;
-use super::util::*;
+use super::board::Layout;
 use super::value::SudokuValue;
+use super::solver::{Solver, Backtracking, Uniqueness};
+use super::difficulty::{self, Difficulty, Rating};
+use super::settings::Settings;
+use super::symmetry::Symmetry;
+use super::size::Size;
+use super::constraint::Constraint;
+use super::regions::Regions;
+use super::error::SudokuError;
+use super::format::Format;
 use std::convert::TryFrom;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
+// generation gives up on the requested difficulty after this many 9x9 puzzles,
+// bigger boards get fewer attempts and smaller ones more
+const MAX_ATTEMPTS: usize = 2000;
+// variants that remove every hint they can, or as many as the difficulty allows,
+// their puzzles take much longer to minimize and rate
+const MAX_MINIMAL_VARIANT_ATTEMPTS: usize = 100;
+
+/// Serialized as `{"cells": "53..7....6..195..."}` with the values as a [Format::Line],
+/// and with the `regions` if they aren't the squares and the `constraints` if there are any.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(into = "SudokuData", try_from = "SudokuData"))]
 pub struct Sudoku {
-    board: [[SudokuValue; 9]; 9]
+    size: Size,
+    board: Vec<Vec<SudokuValue>>,
+    /// The squares, or the irregular regions of a Jigsaw Sudoku.
+    regions: Regions,
+    /// Rules of the variant on top of the rows, columns and regions.
+    constraints: Vec<Constraint>
 }
 
 impl Sudoku {
-    /// Populates a Sudoku board with n hints.
-    /// Returns `(unsolved sudoku, solved sudoku)`.
-    pub fn new(n: usize) -> (Self, Self) {
-        let arr = [[SudokuValue::Empty; 9]; 9];
-        let mut sudoku = Sudoku{ board: arr };
-        sudoku.fill(0, 0).unwrap();
-        let solution = sudoku.clone();
-        sudoku.decimate(n).unwrap();
+    /// Creates a board without any values.
+    pub fn empty(size: Size) -> Self {
+        Sudoku { size, board: vec![vec![SudokuValue::Empty; size.side()]; size.side()], regions: Regions::squares(size), constraints: Vec::new() }
+    }
+
+    /// Returns the dimensions of the board.
+    pub fn size(&self) -> Size {
+        self.size
+    }
 
-        (sudoku, solution)
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
+
+    /// Returns where the board is drawn, bordered if the regions aren't squares
+    /// or if constraints are drawn between the cells, with room for the clues of constraints outside of the grid.
+    pub fn layout(&self) -> Layout {
+        let bordered = !self.regions.is_squares(self.size) || self.constraints.iter().any(|constraint| constraint.is_drawn_between_cells());
+        let clues = self.constraints.iter().any(|constraint| constraint.is_drawn_outside_grid());
+        Layout { size: self.size, side: self.size.side(), bordered, clues }
+    }
+
+    /// Returns the rules of the variant, empty for classic Sudokus.
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// Returns every group of cells that can't repeat a digit: the rows, the columns,
+    /// the regions and then the [Constraint::unique_cells] of the constraints.
+    pub fn units(&self) -> Vec<Vec<(usize, usize)>> {
+        let n = self.size.side();
+        let mut units = Vec::with_capacity(3 * n + self.constraints.len());
+        units.extend((0..n).map(|r| (0..n).map(|c| (r, c)).collect()));
+        units.extend((0..n).map(|c| (0..n).map(|r| (r, c)).collect()));
+        units.extend((0..n).map(|s| self.regions.cells(s)));
+        units.extend(self.constraints.iter().filter_map(|constraint| constraint.unique_cells()).map(|cells| cells.to_vec()));
+        units
+    }
+
+    /// Generates puzzles until one is rated with the requested difficulty.
+    /// The puzzle has as many hints as the difficulty gives for its size, or as few as possible
+    /// in minimal mode or for variants like [Killer](super::variant::Variant::Killer),
+    /// and the hints are placed symmetrically, see [Sudoku::decimate].
+    /// The same seed and settings always generate the same puzzle.
+    /// If no puzzle is rated with the difficulty, like a hard 4x4 puzzle, the one with the closest rating is returned
+    /// and the game shows the difficulty it's rated instead.
+    /// Returns `(unsolved sudoku, solved sudoku)`, or an error if no puzzle could be generated in any attempt.
+    pub fn new(settings: &Settings, seed: u64) -> Result<(Self, Self), SudokuError> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        // the puzzle with the closest rating, used if the difficulty can't be reached
+        let mut closest: Option<(usize, Sudoku, Sudoku)> = None;
+        let max_attempts = if settings.variant.is_minimal() || settings.variant.is_reduced() { MAX_MINIMAL_VARIANT_ATTEMPTS } else { MAX_ATTEMPTS };
+        let attempts = max_attempts * Size::NINE.cells() / settings.size.cells();
+        for _ in 0..attempts {
+            let mut sudoku = Sudoku::empty(settings.size);
+            sudoku.constraints = settings.variant.rules(settings.size);
+            sudoku.constraints.extend(settings.pieces().into_iter().flat_map(|piece| piece.rules(settings.size)));
+            sudoku.fill(&mut rng)?;
+            // random fills with irregular regions can take very long, so the regions are shaped around the solution
+            sudoku.regions = settings.variant.regions(&sudoku, &mut rng);
+            let clues = settings.variant.clues(&sudoku, &mut rng);
+            let first_clue = sudoku.constraints.len();
+            sudoku.constraints.extend(clues);
+            let solution = sudoku.clone();
+
+            let decimated = if settings.minimal || settings.variant.is_minimal() {
+                sudoku.minimize(settings.symmetry, &mut rng);
+                true
+            } else if settings.variant.is_reduced() {
+                sudoku.reduce(settings.difficulty, settings.symmetry, &mut rng);
+                if settings.variant.thins_clues() {
+                    sudoku.thin_clues(first_clue, settings.difficulty, &mut rng);
+                }
+                true
+            } else {
+                sudoku.decimate(settings.difficulty.hints(settings.size), settings.symmetry, &mut rng).is_ok()
+            };
+            if !decimated {
+                continue;
+            }
+            let difficulty = Rating::new(&sudoku).difficulty();
+            if difficulty == Some(settings.difficulty) {
+                return Ok((sudoku, solution));
+            }
+            let distance = difficulty::distance(difficulty, settings.difficulty);
+            if closest.as_ref().is_none_or(|(closest, _, _)| distance < *closest) {
+                closest = Some((distance, sudoku, solution));
+            }
+        }
+        match closest {
+            Some((_, sudoku, solution)) => Ok((sudoku, solution)),
+            None => Err(SudokuError::Generation(format!("No puzzle could be generated in {} attempts", attempts)))
+        }
     }
 
     /// Removes elements from a filled sudoku until there
     /// are `n` elements left, while having only 1 solution.
-    pub fn decimate(&mut self, n: usize) -> Result<(), String> {
+    /// Elements are removed together with the cells they are mapped to by the symmetry,
+    /// so with a symmetry there can be up to 3 elements less than `n` left.
+    /// Returns an error if the Sudoku can't lose any more elements before reaching `n`,
+    /// the board is left with the elements that could be removed.
+    pub fn decimate<R: Rng>(&mut self, n: usize, symmetry: Symmetry, rng: &mut R) -> Result<(), SudokuError> {
         // < 17 is impossible
-        if 17 > n {
-            return Err(format!("It is impossible to create a Sudoku with an unique solution, with less than 17 hints. Input was {} hints.", n));
-        } else if n > 81 {
-            return Err(format!("Impossible to create a Sudoku with {} hints. A Sudoku has just 81 fields.", n));
-        } else if n == 81 {
-            return Ok(());
+        if self.size == Size::NINE && 17 > n {
+            return Err(SudokuError::Generation(format!("It is impossible to create a Sudoku with an unique solution, with less than 17 hints. Input was {} hints.", n)));
+        } else if n > self.size.cells() {
+            return Err(SudokuError::Generation(format!("Impossible to create a Sudoku with {} hints. A {} Sudoku has just {} fields.", n, self.size, self.size.cells())));
         }
 
-        let mut rng = rand::thread_rng();
-        // capacity = sudoku_size - number_remaining_elements + 1 (last element doesn't have to be popped, because then the function returns)
-        let mut stack: Vec<(SudokuValue, Vec<usize>)> = Vec::with_capacity(9*9 - n + 1);
-        let mut all_indices = (0..9).permutations(2).collect_vec();
-        loop {
-            all_indices.shuffle(&mut rng);
-            for index in &all_indices {
-                if self.board[index[0]][index[1]] == SudokuValue::Empty {
-                    continue;
-                }
-                // remove from the board
-                stack.push((self.board[index[0]][index[1]], index.clone()));
-                self.board[index[0]][index[1]] = SudokuValue::Empty;
-                if self.solve(0, 0) != 1 {
-                    // if there isn't one distinct solution revert
-                    match stack.pop() {
-                        Some((val, index)) => {
-                            self.board[index[0]][index[1]] = val;
-                        },
-                        None => return Err(format!("Popped with no elements left!"))
-                    }
-                }
-                if self.count(SudokuValue::Empty) == 81-n {
-                    return Ok(());
-                }
+        let left = self.remove_hints(n, symmetry, rng);
+        if left <= n {
+            Ok(())
+        } else {
+            Err(SudokuError::Generation(format!("No more hints can be removed with {} hints left, {} were requested.", left, n)))
+        }
+    }
+
+    /// Removes elements from a filled sudoku until none of them can be removed without losing the unique solution.
+    /// With a symmetry the orbits of the symmetry are removed first, then the single elements of the orbits
+    /// that can still be removed, so the puzzle is minimal but can be a few hints short of symmetric.
+    /// Returns the number of hints left.
+    pub fn minimize<R: Rng>(&mut self, symmetry: Symmetry, rng: &mut R) -> usize {
+        let left = self.remove_hints(0, symmetry, rng);
+        if symmetry == Symmetry::None {
+            return left;
+        }
+        self.remove_hints(0, Symmetry::None, rng)
+    }
+
+    /// Removes the orbits of the symmetry in random order, skipping every orbit without which
+    /// the puzzle would be rated harder than the difficulty or couldn't be solved with logic at all.
+    /// A puzzle the logical solver solves has a unique solution, so no search is needed.
+    /// Returns the number of hints left.
+    pub fn reduce<R: Rng>(&mut self, difficulty: Difficulty, symmetry: Symmetry, rng: &mut R) -> usize {
+        let mut orbits = symmetry.orbits(self.size.side());
+        orbits.shuffle(rng);
+        for orbit in &orbits {
+            let removed = orbit.iter().map(|&(r, c)| (r, c, self.board[r][c])).collect_vec();
+            for &(r, c) in orbit {
+                self.board[r][c] = SudokuValue::Empty;
             }
-            // if this branch didn't have any unique solutions revert
-            match stack.pop() {
-                Some((val, index)) => {
-                    self.board[index[0]][index[1]] = val;
-                },
-                None => return Err(format!("Popped with no elements left!"))
+            if difficulty::is_harder(Rating::new(self).difficulty(), difficulty) {
+                for (r, c, val) in removed {
+                    self.board[r][c] = val;
+                }
             }
         }
+        self.hints()
     }
 
-    /// Returns the number of different solutions of a given Sudoku
-    /// call this function with `sudoku.solve(0, 0)`.
-    pub fn solve(&mut self, r: usize, c: usize) -> i32 {
-        // code is similar to Sudoku::fill, maybe a helper function can combine some of the code
-        let mut counter = 0;
-        if self.board[r][c] != SudokuValue::Empty {
-            let next_r: usize;
-            let next_c: usize;
-            match (r, c) {
-                // impossible, because then every field, would be filled but it wouldn't be solved
-                // (8, 8) => ...
-                (_, 8) => {
-                    next_r = r + 1;
-                    next_c = 0;
-                },
-                (_, _) => {
-                    next_r = r;
-                    next_c = c + 1;
+    /// Removes the orbits of the symmetry in random order until at most `n` hints are left,
+    /// skipping every orbit that would allow a second solution. Returns the number of hints left.
+    fn remove_hints<R: Rng>(&mut self, n: usize, symmetry: Symmetry, rng: &mut R) -> usize {
+        let mut orbits = symmetry.orbits(self.size.side());
+        orbits.shuffle(rng);
+        for orbit in &orbits {
+            if self.hints() <= n {
+                break;
+            }
+            // remove from the board
+            let removed = orbit.iter().map(|&(r, c)| (r, c, self.board[r][c])).collect_vec();
+            for &(r, c) in orbit {
+                self.board[r][c] = SudokuValue::Empty;
+            }
+            if self.uniqueness() != Uniqueness::Unique {
+                // if there isn't one distinct solution revert
+                for (r, c, val) in removed {
+                    self.board[r][c] = val;
                 }
             }
-            if self.solved() {
-                // this recursion reached it's end with a completed Sudoku so return.
-                // it isn't possible for another number to fit this r, c value.
-                // Mutating the original Sudoku, so it has to be restored
-                return 1;
-            } else {
-                // don't return, other numbers in this field could lead to additional solutions
-                return counter + self.solve(next_r, next_c);
-                // Mutating the original Sudoku, so it has to be restored
+        }
+        // removing more hints only adds solutions, so orbits that failed once can't be removed later
+        self.hints()
+    }
+
+    /// Removes the clues of the variant in random order after the hints, skipping every clue without which
+    /// the puzzle would be rated harder than the difficulty, like [Sudoku::reduce].
+    /// The constraints from `first` on are the clues, the ones before are rules that always stay.
+    pub fn thin_clues<R: Rng>(&mut self, first: usize, difficulty: Difficulty, rng: &mut R) {
+        let mut clues = self.constraints.split_off(first);
+        clues.shuffle(rng);
+        while let Some(clue) = clues.pop() {
+            // rated with the clues kept so far and the ones that aren't tried yet, but without this one
+            self.constraints.extend(clues.iter().cloned());
+            let needed = difficulty::is_harder(Rating::new(self).difficulty(), difficulty);
+            self.constraints.truncate(self.constraints.len() - clues.len());
+            if needed {
+                self.constraints.push(clue);
             }
         }
-        let sudoku_numbers: [SudokuValue; 9] = SudokuValue::get_number_array();
-        for number in &sudoku_numbers {
-            self.board[r][c] = *number;
-            if self.check(r, c) {
-                let next_r: usize;
-                let next_c: usize;
-                match (r, c) {
-                    // impossible, because then every field, would be filled but it wouldn't be solved
-                    // (8, 8) => ...
-                    (_, 8) => {
-                        next_r = r + 1;
-                        next_c = 0;
-                    },
-                    (_, _) => {
-                        next_r = r;
-                        next_c = c + 1;
-                    }
+        self.constraints[first..].sort_unstable();
+    }
+
+    /// Returns the number of fields that have a value.
+    pub fn hints(&self) -> usize {
+        self.size.cells() - self.count(SudokuValue::Empty)
+    }
+
+    /// Returns the hints that can be removed on their own, without losing the unique solution.
+    pub fn redundant_hints(&self) -> Vec<(usize, usize)> {
+        let mut sudoku = self.clone();
+        let mut redundant = Vec::new();
+        for r in 0..self.size.side() {
+            for c in 0..self.size.side() {
+                let val = self.board[r][c];
+                if val == SudokuValue::Empty {
+                    continue;
                 }
-                if self.solved() {
-                    // this recursion reached it's end with a completed Sudoku so return.
-                    // it isn't possible for another number to fit this r, c value.
-                    // Mutating the original Sudoku, so it has to be restored
-                    self.board[r][c] = SudokuValue::Empty;
-                    return 1;
-                } else {
-                    // don't return, other numbers in this field could lead to additional solutions
-                    counter += self.solve(next_r, next_c);
-                    // Mutating the original Sudoku, so it has to be restored
-                    self.board[r][c] = SudokuValue::Empty;
+                sudoku.board[r][c] = SudokuValue::Empty;
+                if sudoku.uniqueness() == Uniqueness::Unique {
+                    redundant.push((r, c));
                 }
+                sudoku.board[r][c] = val;
             }
         }
-        // Mutating the original Sudoku, so it has to be restored
-        self.board[r][c] = SudokuValue::Empty;
-        counter
-    }
-
-    /// Fills an empty sudoku board.
-    /// Row and col are the first empty index.
-    /// Call this function with `sudoku.fill(0, 0)` to fill an empty board completely.
-    pub fn fill(&mut self, r: usize, c: usize) -> Result<(), ()> {
-        let mut rng = rand::thread_rng();
-        let mut sudoku_numbers: [SudokuValue; 9] = SudokuValue::get_number_array();
-        sudoku_numbers.shuffle(&mut rng);
-        for number in &sudoku_numbers {
-            self.board[r][c] = *number;
-            if self.check(r, c) {
-                let next_r: usize;
-                let next_c: usize;
-                match (r,c) {
-                    (8, 8) => return Ok(()),
-                    (_, 8) => {next_r = r + 1; next_c = 0;},
-                    (_, _) => {next_r = r; next_c = c + 1;}
-                }
-                if self.fill(next_r, next_c).is_ok() {
-                    return Ok(());
-                } else {
-                    self.board[r][c] = SudokuValue::Empty;
-                    continue;
-                }
+        redundant
+    }
 
-            }
+    /// Returns `true` if the Sudoku has a unique solution and none of its hints can be removed.
+    pub fn is_minimal(&self) -> bool {
+        self.uniqueness() == Uniqueness::Unique && self.redundant_hints().is_empty()
+    }
+
+    /// Returns the number of different solutions of a given Sudoku,
+    /// but stops searching as soon as `limit` solutions are found.
+    /// Uses the [Backtracking] solver, which is faster than Dancing Links on the puzzles we generate.
+    /// Use `usize::MAX` as limit to count every solution.
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        Backtracking.count(self, limit)
+    }
+
+    /// Returns a solution of the Sudoku, `None` if it has none.
+    /// Check [Sudoku::uniqueness] first if it has to be the only one.
+    pub fn solution(&self) -> Option<Sudoku> {
+        Backtracking.solve(self)
+    }
+
+    /// Returns whether the Sudoku has no, exactly one or multiple solutions.
+    pub fn uniqueness(&self) -> Uniqueness {
+        match self.count_solutions(2) {
+            0 => Uniqueness::None,
+            1 => Uniqueness::Unique,
+            _ => Uniqueness::Multiple
+        }
+    }
+
+    /// Fills the empty fields of the sudoku board with random numbers, so that it is solved.
+    /// Call this function on an empty board to create a random solved Sudoku.
+    /// Returns an error and leaves the board as it was if it can't be solved.
+    pub fn fill<R: RngCore>(&mut self, rng: &mut R) -> Result<(), SudokuError> {
+        Backtracking.fill(self, rng)
+    }
+
+    /// Returns the board as row-major digits, `0` is an empty field.
+    pub fn to_cells(&self) -> Vec<u8> {
+        self.board.iter().flatten().map(|&val| val as u8).collect()
+    }
+
+    /// Replaces the values of the board with row-major digits, `0` is an empty field, inverse of [Sudoku::to_cells].
+    /// Returns an error and leaves the board as it was if there isn't a digit for every cell or a digit is too big for the board.
+    pub fn set_cells(&mut self, cells: &[u8]) -> Result<(), SudokuError> {
+        let side = self.size.side();
+        if cells.len() != self.size.cells() {
+            return Err(SudokuError::Parse(format!("{} digits don't fit a {} board with {} cells", cells.len(), self.size, self.size.cells())));
+        }
+        if let Some(&digit) = cells.iter().find(|&&digit| digit as usize > side) {
+            return Err(SudokuError::Parse(format!("{} is not a value of a {} board", digit, self.size)));
+        }
+        for (i, &cell) in cells.iter().enumerate() {
+            self.board[i / side][i % side] = SudokuValue::try_from(cell as i32)?;
         }
-        // all numbers failed => impossible to continue from this => return false and make the recursion above try another number
-        self.board[r][c] = SudokuValue::Empty;
-        Err(())
+        Ok(())
     }
 
     /// Returns `true` if the sudoku is completely solved.
@@ -195,77 +331,74 @@ impl Sudoku {
     /// *** **X ***
     /// *** *** **X
     /// ```
+    /// Other sizes use the same pattern, every row, column and square has exactly one X.
+    /// Irregular regions don't follow the pattern, so they are checked one by one.
+    /// The constraints of a variant are checked as well.
     pub fn check_all(&self) -> bool {
-        let indices = [(0, 0), (1, 3), (2, 6), (3, 1), (4, 4), (5, 7), (6, 2), (7, 5), (8, 8)];
-        for (r, c) in &indices {
-            if !self.check(*r, *c) {
-                return false;
-            }
-        }
-        true
+        let (box_rows, box_cols) = (self.size.box_rows, self.size.box_cols);
+        (0..self.size.side()).all(|r| self.check(r, (r % box_rows) * box_cols + r / box_rows) == Ok(true))
+            && (self.regions.is_squares(self.size) || (0..self.size.side()).all(|s| {
+                let mut values = self.regions.cells(s).into_iter().map(|(r, c)| &self.board[r][c]).collect_vec();
+                has_only_unique_elements(&mut values, &SudokuValue::Empty)
+            }))
+            && self.constraints.iter().all(|constraint| self.check_constraint(constraint))
     }
 
-    /// Returns `true` if the row, column and square of the indices have no duplicate SudokuValue.
-    pub fn check(&self, r: usize, c: usize) -> bool {
-        let mut reference_arr_row: [&SudokuValue; 9] = match self.get_row(r) {
-            Some(x) => x,
-            None => panic!("IndexError")
-        };
-        let mut reference_arr_col: [&SudokuValue; 9] = match self.get_column(c) {
-            Some(x) => x,
-            None => panic!("IndexError")
-        };
-        let mut reference_arr_square: [&SudokuValue; 9] = match self.get_square(r, c) {
-            Some(x) => x,
-            None => panic!("IndexError")
+    /// Returns `true` if the row, column and region of the indices have no duplicate SudokuValue
+    /// and the constraints the cell is part of can still be satisfied.
+    /// Returns an error for indices outside of the board.
+    pub fn check(&self, r: usize, c: usize) -> Result<bool, SudokuError> {
+        let units = self.check_units(r, c).ok_or(SudokuError::InvalidIndex { r, c, side: self.size.side() })?;
+        Ok(units && self.constraints.iter().filter(|constraint| constraint.contains(r, c)).all(|constraint| self.check_constraint(constraint)))
+    }
+
+    /// Returns `true` if the constraint has no duplicate SudokuValue and can still be satisfied.
+    pub fn check_constraint(&self, constraint: &Constraint) -> bool {
+        let unique = match constraint.unique_cells() {
+            Some(cells) => {
+                let mut values = cells.iter().map(|&(r, c)| &self.board[r][c]).collect_vec();
+                has_only_unique_elements(&mut values, &SudokuValue::Empty)
+            },
+            None => true
         };
+        unique && constraint.allows(self.size.side(), |r, c| self.board[r][c] as u8)
+    }
 
-        has_only_unique_elements(&mut reference_arr_row, &SudokuValue::Empty)
+    /// Returns every pair of cells that share a unit and have the same value, see [Sudoku::units].
+    /// Every pair is returned once, with the row-major first cell first.
+    pub fn conflicts(&self) -> Vec<((usize, usize), (usize, usize))> {
+        self.units().iter()
+            .flat_map(|unit| unit.iter().copied().tuple_combinations())
+            .filter(|&((r1, c1), (r2, c2))| self.board[r1][c1] != SudokuValue::Empty && self.board[r1][c1] == self.board[r2][c2])
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .unique()
+            .collect()
+    }
+
+    /// Returns `None` for indices outside of the board.
+    fn check_units(&self, r: usize, c: usize) -> Option<bool> {
+        let mut reference_arr_row: Vec<&SudokuValue> = self.get_row(r)?;
+        let mut reference_arr_col: Vec<&SudokuValue> = self.get_column(c)?;
+        let mut reference_arr_square: Vec<&SudokuValue> = self.get_square(r, c)?;
+
+        Some(has_only_unique_elements(&mut reference_arr_row, &SudokuValue::Empty)
          && has_only_unique_elements(&mut reference_arr_col, &SudokuValue::Empty)
-         && has_only_unique_elements(&mut reference_arr_square, &SudokuValue::Empty)
+         && has_only_unique_elements(&mut reference_arr_square, &SudokuValue::Empty))
     }
 
     /// Returns how often the SudokuValue is on the board.
     pub fn count(&self, val: SudokuValue) -> usize {
-        let mut count = 0;
-        for r in 0..9 {
-            for c in 0..9 {
-                if self.board[r][c] == val {
-                    count += 1;
-                }
-            }
-        }
-        count
+        self.board.iter().flatten().filter(|&&x| x == val).count()
     }
 
     /// Returns the specified row.
-    pub fn get_row(&self, r: usize) -> Option<[&SudokuValue; 9]> {
-        return Some([
-            self.board.get(r)?.get(0)?,
-            self.board.get(r)?.get(1)?,
-            self.board.get(r)?.get(2)?,
-            self.board.get(r)?.get(3)?,
-            self.board.get(r)?.get(4)?,
-            self.board.get(r)?.get(5)?,
-            self.board.get(r)?.get(6)?,
-            self.board.get(r)?.get(7)?,
-            self.board.get(r)?.get(8)?
-        ]);
+    pub fn get_row(&self, r: usize) -> Option<Vec<&SudokuValue>> {
+        Some(self.board.get(r)?.iter().collect())
     }
 
     /// Returns the specified column.
-    pub fn get_column(&self, c: usize) -> Option<[&SudokuValue; 9]> {
-        return Some([
-            self.board.get(0)?.get(c)?,
-            self.board.get(1)?.get(c)?,
-            self.board.get(2)?.get(c)?,
-            self.board.get(3)?.get(c)?,
-            self.board.get(4)?.get(c)?,
-            self.board.get(5)?.get(c)?,
-            self.board.get(6)?.get(c)?,
-            self.board.get(7)?.get(c)?,
-            self.board.get(8)?.get(c)?
-        ]);
+    pub fn get_column(&self, c: usize) -> Option<Vec<&SudokuValue>> {
+        self.board.iter().map(|row| row.get(c)).collect()
     }
 
     // Example:
@@ -279,57 +412,188 @@ impl Sudoku {
     // 777 888 999
     // 777 888 999
     // 777 888 999
-    /// Returns the square in which the indices lie.
-    pub fn get_square(&self, r: usize, c: usize) -> Option<[&SudokuValue; 9]> {
-        return Some([
-            self.board.get((r / 3) * 3 + 0)?.get((c / 3) * 3 + 0)?,
-            self.board.get((r / 3) * 3 + 0)?.get((c / 3) * 3 + 1)?,
-            self.board.get((r / 3) * 3 + 0)?.get((c / 3) * 3 + 2)?,
-            self.board.get((r / 3) * 3 + 1)?.get((c / 3) * 3 + 0)?,
-            self.board.get((r / 3) * 3 + 1)?.get((c / 3) * 3 + 1)?,
-            self.board.get((r / 3) * 3 + 1)?.get((c / 3) * 3 + 2)?,
-            self.board.get((r / 3) * 3 + 2)?.get((c / 3) * 3 + 0)?,
-            self.board.get((r / 3) * 3 + 2)?.get((c / 3) * 3 + 1)?,
-            self.board.get((r / 3) * 3 + 2)?.get((c / 3) * 3 + 2)?
-        ]);
+    /// Returns the square, or the irregular region, in which the indices lie.
+    pub fn get_square(&self, r: usize, c: usize) -> Option<Vec<&SudokuValue>> {
+        self.get(r, c)?;
+        Some(self.regions.cells(self.regions.region(r, c)).into_iter()
+            .map(|(r, c)| &self.board[r][c])
+            .collect())
     }
 
     /// Returns the SudokuValue at the specified indices.
     pub fn get(&self, r: usize, c: usize) -> Option<&SudokuValue> {
-        return self.board.get(r)?.get(c);
+        self.board.get(r)?.get(c)
     }
 
-    /// Sets the value at the specified indices.
-    pub fn set(&mut self, r: usize, c: usize, val: SudokuValue) {
-        self.board[r][c] = val;
+    /// Sets the value at the specified indices, returns an error for indices outside of the board.
+    pub fn set(&mut self, r: usize, c: usize, val: SudokuValue) -> Result<(), SudokuError> {
+        match self.board.get_mut(r).and_then(|row| row.get_mut(c)) {
+            Some(cell) => {
+                *cell = val;
+                Ok(())
+            },
+            None => Err(SudokuError::InvalidIndex { r, c, side: self.size.side() })
+        }
     }
 }
 
 impl TryFrom<&str> for Sudoku {
-    type Error = String;
+    type Error = SudokuError;
 
-    /// The provided `&str` has to have a length of 81 and is only allowed to have the chars in 0-9.
+    /// Reads a single puzzle in any [Format], which is detected from the text.
+    /// A board has as many cells as a supported [Size], 81 for a 9x9 board.
+    /// Cells are `.` or `0` for a blank, 1-9 and, for boards bigger than 9x9, the letters used by [SudokuValue].
     fn try_from(sud_str: &str) -> Result<Self, Self::Error> {
-        if sud_str.len() != 81 {
-            return Err(format!("The provided String does not meet the required length of 81, it's length is {}", sud_str.len()));
-        }
-        let mut sud = Sudoku{board: [[SudokuValue::Empty; 9]; 9]};
-        let mut col = 0;
-        let mut row = 0;
-        let mut err = false;
-        for c in sud_str.chars() {
-            match c.to_digit(10) {
-                Some(x) => sud.board[row][col] = SudokuValue::try_from(i32::try_from(x).unwrap()).unwrap(),
-                None => err = true
+        Format::detect(sud_str).read(sud_str)
+    }
+}
+
+/// How a [Sudoku] is serialized.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SudokuData {
+    cells: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    regions: Option<Regions>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    constraints: Vec<Constraint>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Sudoku> for SudokuData {
+    fn from(sudoku: Sudoku) -> Self {
+        let cells = Format::Line.write(&sudoku).trim_end().to_string();
+        let regions = Some(sudoku.regions).filter(|regions| !regions.is_squares(sudoku.size));
+        SudokuData { cells, regions, constraints: sudoku.constraints }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SudokuData> for Sudoku {
+    type Error = SudokuError;
+
+    fn try_from(data: SudokuData) -> Result<Self, Self::Error> {
+        let mut sudoku = Format::Line.read(&data.cells)?;
+        let side = sudoku.size.side();
+        if let Some(regions) = data.regions {
+            // every region of the board has side cells
+            if regions.cells(0).len() != side {
+                return Err(SudokuError::Parse(format!("The regions don't fit a {} board", sudoku.size)));
             }
-            match col {
-                8 => {col = 0; row += 1}
-                _ => col += 1
+            sudoku.regions = regions;
+        }
+        if let Some(&(r, c)) = data.constraints.iter().flat_map(|constraint| constraint.cells()).find(|&&(r, c)| r >= side || c >= side) {
+            return Err(SudokuError::InvalidIndex { r, c, side });
+        }
+        sudoku.constraints = data.constraints;
+        Ok(sudoku)
+    }
+}
+
+fn has_only_unique_elements<T>(reference_slice: &mut [&T], ignore: &T) -> bool  where T: Ord{
+    // sort and return false if neighbors are the same
+    reference_slice.sort();
+    let length = reference_slice.len();
+    for i in 0..(length - 1) {
+        if reference_slice[i] != ignore && reference_slice.get(i) == reference_slice.get(i + 1) {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    #[test]
+    fn counts_solutions_up_to_the_limit() {
+        let empty = Sudoku::empty(Size::NINE);
+        assert_eq!(empty.count_solutions(2), 2);
+        assert_eq!(empty.count_solutions(5), 5);
+        assert_eq!(empty.uniqueness(), Uniqueness::Multiple);
+        let puzzle = Sudoku::try_from(PUZZLE).unwrap();
+        assert_eq!(puzzle.count_solutions(usize::MAX), 1);
+        assert_eq!(puzzle.uniqueness(), Uniqueness::Unique);
+        // a 5 in the last cell of the first row leaves no digit for the cell before it
+        let contradiction = Sudoku::try_from(format!("53..7...5{}", &PUZZLE[9..]).as_str()).unwrap();
+        assert_eq!(contradiction.count_solutions(2), 0);
+        assert_eq!(contradiction.uniqueness(), Uniqueness::None);
+    }
+
+    #[test]
+    fn rejects_cells_and_indices_outside_of_the_board() {
+        let puzzle = Sudoku::try_from(PUZZLE).unwrap();
+        let mut sudoku = puzzle.clone();
+        assert!(sudoku.set_cells(&[0; 80]).is_err());
+        assert!(sudoku.set_cells(&[10; 81]).is_err());
+        assert_eq!(sudoku, puzzle);
+        sudoku.set_cells(&[0; 81]).unwrap();
+        assert_eq!(sudoku, Sudoku::empty(Size::NINE));
+
+        assert_eq!(puzzle.check(8, 8), Ok(true));
+        assert_eq!(puzzle.check(9, 0), Err(SudokuError::InvalidIndex { r: 9, c: 0, side: 9 }));
+        assert_eq!(puzzle.check(0, 9), Err(SudokuError::InvalidIndex { r: 0, c: 9, side: 9 }));
+        // the 5 in the first cell is repeated in its row
+        let mut repeated = puzzle;
+        repeated.set(0, 8, SudokuValue::Five).unwrap();
+        assert_eq!(repeated.check(0, 0), Ok(false));
+    }
+
+    #[test]
+    fn minimizes_puzzles() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for (size, symmetry) in [(Size::SIX, Symmetry::None), (Size::NINE, Symmetry::None), (Size::NINE, Symmetry::Rotational180)] {
+            let mut sudoku = Sudoku::empty(size);
+            sudoku.fill(&mut rng).unwrap();
+            let solution = sudoku.clone();
+            let left = sudoku.minimize(symmetry, &mut rng);
+            assert_eq!(left, sudoku.hints());
+            assert!(sudoku.is_minimal(), "{:?}", symmetry);
+            assert_eq!(sudoku.solution(), Some(solution));
+        }
+    }
+
+    #[test]
+    fn finds_redundant_hints() {
+        // a puzzle with 17 hints, the fewest a 9x9 puzzle can have, so none of them is redundant
+        let minimal = Sudoku::try_from("...8.1..........435............7.8........1...2..3....6......75..34........2..6..").unwrap();
+        assert!(minimal.is_minimal());
+        assert_eq!(minimal.redundant_hints(), vec![]);
+        let mut puzzle = minimal.clone();
+        // the added hint can be removed again, while every other hint is still needed
+        puzzle.set(0, 0, minimal.solution().unwrap().board[0][0]).unwrap();
+        assert_eq!(puzzle.redundant_hints(), vec![(0, 0)]);
+        assert!(!puzzle.is_minimal());
+    }
+
+    #[test]
+    fn generates_puzzles_rated_with_the_difficulty() {
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            for seed in 0..3 {
+                let settings = Settings { difficulty, ..Settings::default() };
+                let (puzzle, solution) = Sudoku::new(&settings, seed).unwrap();
+                assert_eq!(Rating::new(&puzzle).difficulty(), Some(difficulty), "{} {}", difficulty, seed);
+                assert_eq!(puzzle.solution(), Some(solution));
             }
         }
-        match err {
-            true => Err(String::from("SudokuValue has to be in the range 0..=9")),
-            false => Ok(sud)
+    }
+
+    #[test]
+    fn rates_the_hint_counts_of_other_sizes_with_their_difficulty() {
+        // medium has only a few hints more than hard, but on boards with enough cells the ratings still tell them apart,
+        // 4x4 and 6x6 boards have too few cells and some of their puzzles are rated easier
+        for size in [Size::EIGHT, Size::TWELVE] {
+            for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+                for seed in 0..3 {
+                    let settings = Settings { size, difficulty, ..Settings::default() };
+                    let (puzzle, _) = Sudoku::new(&settings, seed).unwrap();
+                    assert_eq!(puzzle.hints(), difficulty.hints(size), "{} {} {}", size, difficulty, seed);
+                    assert_eq!(Rating::new(&puzzle).difficulty(), Some(difficulty), "{} {} {}", size, difficulty, seed);
+                }
+            }
         }
     }
 }
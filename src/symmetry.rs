@@ -0,0 +1,146 @@
+use std::fmt::Formatter;
+
+/// Symmetry of the hints of a generated puzzle.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Symmetry {
+    None,
+    /// 180° rotation around the center.
+    Rotational180,
+    /// 90° rotation around the center.
+    Rotational90,
+    /// Mirrored at the horizontal center line.
+    Horizontal,
+    /// Mirrored at the vertical center line.
+    Vertical,
+    /// Mirrored at the diagonal from the top left to the bottom right.
+    Diagonal,
+}
+
+impl Symmetry {
+    pub fn all() -> [Symmetry; 6] {
+        [Symmetry::None, Symmetry::Rotational180, Symmetry::Rotational90, Symmetry::Horizontal, Symmetry::Vertical, Symmetry::Diagonal]
+    }
+
+    pub fn next(self) -> Self {
+        let all = Symmetry::all();
+        all[(self.index() + 1) % all.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        let all = Symmetry::all();
+        all[(self.index() + all.len() - 1) % all.len()]
+    }
+
+    fn index(self) -> usize {
+        Symmetry::all().iter().position(|&s| s == self).unwrap()
+    }
+
+    /// Returns the cells that are mapped onto each other by the symmetry, including `(r, c)` itself.
+    /// `side` is the number of rows of the board.
+    pub fn orbit(self, r: usize, c: usize, side: usize) -> Vec<(usize, usize)> {
+        let last = side - 1;
+        let mut orbit = vec![(r, c)];
+        let mut add = |cell| if !orbit.contains(&cell) { orbit.push(cell) };
+        match self {
+            Symmetry::None          => {},
+            Symmetry::Rotational180 => add((last - r, last - c)),
+            Symmetry::Rotational90  => {
+                add((c, last - r));
+                add((last - r, last - c));
+                add((last - c, r));
+            },
+            Symmetry::Horizontal    => add((last - r, c)),
+            Symmetry::Vertical      => add((r, last - c)),
+            Symmetry::Diagonal      => add((c, r))
+        }
+        orbit
+    }
+
+    /// Splits the board into orbits, every cell is in exactly one of them.
+    pub fn orbits(self, side: usize) -> Vec<Vec<(usize, usize)>> {
+        let mut orbits: Vec<Vec<(usize, usize)>> = Vec::new();
+        for r in 0..side {
+            for c in 0..side {
+                if !orbits.iter().any(|orbit| orbit.contains(&(r, c))) {
+                    orbits.push(self.orbit(r, c, side));
+                }
+            }
+        }
+        orbits
+    }
+
+    /// A single letter used in the [PuzzleId](super::puzzle_id::PuzzleId).
+    pub fn code(self) -> char {
+        match self {
+            Symmetry::None          => 'N',
+            Symmetry::Rotational180 => 'R',
+            Symmetry::Rotational90  => 'Q',
+            Symmetry::Horizontal    => 'H',
+            Symmetry::Vertical      => 'V',
+            Symmetry::Diagonal      => 'D'
+        }
+    }
+
+    pub fn from_code(code: char) -> Option<Self> {
+        Symmetry::all().iter().copied().find(|s| s.code() == code)
+    }
+}
+
+impl std::fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Symmetry::None          => "No symmetry",
+            Symmetry::Rotational180 => "180° rotational",
+            Symmetry::Rotational90  => "90° rotational",
+            Symmetry::Horizontal    => "Horizontal mirror",
+            Symmetry::Vertical      => "Vertical mirror",
+            Symmetry::Diagonal      => "Diagonal mirror"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::settings::Settings;
+    use crate::size::Size;
+    use crate::sudoku::Sudoku;
+    use crate::value::SudokuValue;
+
+    #[test]
+    fn orbits_partition_the_board() {
+        for symmetry in Symmetry::all() {
+            for side in [4, 6, 9, 16] {
+                let orbits = symmetry.orbits(side);
+                let cells = orbits.iter().flatten().copied().sorted().collect_vec();
+                assert_eq!(cells, (0..side).cartesian_product(0..side).collect_vec(), "{} {}", symmetry, side);
+                // every cell of an orbit is mapped onto the same cells
+                for orbit in &orbits {
+                    for &(r, c) in orbit {
+                        assert_eq!(symmetry.orbit(r, c, side).into_iter().sorted().collect_vec(),
+                                   orbit.iter().copied().sorted().collect_vec(), "{} {}", symmetry, side);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generates_symmetric_hints() {
+        for symmetry in Symmetry::all() {
+            for size in [Size::SIX, Size::NINE] {
+                let settings = Settings { size, symmetry, difficulty: Difficulty::Easy, ..Settings::default() };
+                let (puzzle, _) = Sudoku::new(&settings, 11).unwrap();
+                let side = size.side();
+                let given = |(r, c): (usize, usize)| puzzle.get(r, c) != Some(&SudokuValue::Empty);
+                assert!((0..side).cartesian_product(0..side).any(given));
+                for (r, c) in (0..side).cartesian_product(0..side).filter(|&cell| given(cell)) {
+                    assert!(symmetry.orbit(r, c, side).into_iter().all(given),
+                            "{} {}: ({}, {})", symmetry, size, r, c);
+                }
+            }
+        }
+    }
+}
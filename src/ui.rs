@@ -1,3 +1,6 @@
+use crossterm::{Result, event};
+use sudoku_game::SudokuValue;
+
 #[derive(Copy, Clone)]
 pub struct Terminal {
     width: u16,
@@ -9,6 +12,14 @@ impl Terminal {
         Terminal { width, height }
     }
 
+    pub fn width(self) -> u16 {
+        self.width
+    }
+
+    pub fn height(self) -> u16 {
+        self.height
+    }
+
     pub fn h_center(self) -> u16 {
         self.width / 2
     }
@@ -37,3 +48,37 @@ impl Terminal {
         }
     }
 }
+
+pub fn read_key_code() -> Result<event::KeyCode> {
+    loop {
+        if let Ok(event::Event::Key(event::KeyEvent { code: k, .. })) = event::read() {
+            return Ok(k);
+        }
+    }
+}
+
+pub fn is_up(kc: event::KeyCode) -> bool {
+    kc == event::KeyCode::Up || kc == event::KeyCode::Char('w')
+}
+
+pub fn is_left(kc: event::KeyCode) -> bool {
+    kc == event::KeyCode::Left || kc == event::KeyCode::Char('a')
+}
+
+pub fn is_down(kc: event::KeyCode) -> bool {
+    kc == event::KeyCode::Down || kc == event::KeyCode::Char('s')
+}
+
+pub fn is_right(kc: event::KeyCode) -> bool {
+    kc == event::KeyCode::Right || kc == event::KeyCode::Char('d')
+}
+
+pub fn key_code_to_sudoku_value(kc: event::KeyCode) -> Option<SudokuValue> {
+    match kc {
+        // lowercase letters are commands, so only uppercase letters are values
+        event::KeyCode::Char(x) if x.is_ascii_digit() || x.is_ascii_uppercase() => SudokuValue::from_char(x),
+        event::KeyCode::Delete => Some(SudokuValue::Empty),
+        event::KeyCode::Backspace => Some(SudokuValue::Empty),
+        _ => None
+    }
+}
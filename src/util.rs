@@ -1,76 +1,144 @@
-use crossterm::{Result, event};
 use box_drawing::light;
-use super::SudokuValue;
-use std::convert::TryFrom;
-
-pub fn read_key_code() -> Result<event::KeyCode> {
-    loop {
-        if let Ok(event::Event::Key(event::KeyEvent { code: k, .. })) = event::read() {
-            return Ok(k);
-        }
+use sudoku_game::board::{Board, Layout};
+use sudoku_game::constraint::Constraint;
+
+/// Returns the lines of the board, from the top to the bottom. Bordered boards get a border between
+/// every two regions, the others a divider between every two squares that are part of the board.
+pub fn bars<B: Board>(board: &B) -> Vec<String> {
+    let layout = board.layout();
+    if layout.bordered {
+        return region_bars(layout.side, |r, c| board.region(r, c));
     }
+    let (box_rows, box_cols) = (layout.size.box_rows, layout.size.box_cols);
+    square_bars(layout, |r, c| board.get(r * box_rows, c * box_cols).map(|_| r * layout.side + c))
 }
 
-pub fn is_up(kc: event::KeyCode) -> bool {
-    kc == event::KeyCode::Up || kc == event::KeyCode::Char('w')
-}
-
-pub fn is_left(kc: event::KeyCode) -> bool {
-    kc == event::KeyCode::Left || kc == event::KeyCode::Char('a')
-}
+// columns left of the grid and rows above it taken by the clues outside of it, like the sums of a Sandwich Sudoku
+const CLUE_WIDTH: i32 = 3;
+const CLUE_HEIGHT: i32 = 2;
 
-pub fn is_down(kc: event::KeyCode) -> bool {
-    kc == event::KeyCode::Down || kc == event::KeyCode::Char('s')
-}
-
-pub fn is_right(kc: event::KeyCode) -> bool {
-    kc == event::KeyCode::Right || kc == event::KeyCode::Char('d')
-}
-
-pub fn key_code_to_sudoku_value(kc: event::KeyCode) -> Option<SudokuValue> {
-    match kc {
-        event::KeyCode::Char(x) if ('0'..='9').contains(&x) => SudokuValue::try_from(x.to_digit(10).unwrap() as i32).ok(),
-        event::KeyCode::Delete => Some(SudokuValue::Empty),
-        event::KeyCode::Backspace => Some(SudokuValue::Empty),
-        _ => None
+/// Number of columns the board takes, a 9x9 board is 25 wide, or 19 if it's bordered.
+pub fn grid_width(layout: Layout) -> i32 {
+    let (side, box_cols) = (layout.side as i32, layout.size.box_cols as i32);
+    if layout.bordered {
+        2 * side + 1
+    } else {
+        2 * side + 2 * (side / box_cols) + 1
     }
 }
 
-pub fn top_bar() -> String {
-    bar(light::DOWN_RIGHT, light::DOWN_LEFT, light::DOWN_HORIZONTAL, light::HORIZONTAL)
+/// Number of rows the board takes, a 9x9 board is 13 high, or 19 if it's bordered.
+pub fn grid_height(layout: Layout) -> i32 {
+    let (side, box_rows) = (layout.side as i32, layout.size.box_rows as i32);
+    if layout.bordered {
+        2 * side + 1
+    } else {
+        side + side / box_rows + 1
+    }
 }
 
-pub fn bot_bar() -> String {
-    bar(light::UP_RIGHT, light::UP_LEFT, light::UP_HORIZONTAL, light::HORIZONTAL)
+/// Number of columns left of the grid and rows above it that are kept free for clues outside of the grid.
+pub fn clue_margin(layout: Layout) -> (i32, i32) {
+    if layout.clues {
+        (CLUE_WIDTH, CLUE_HEIGHT)
+    } else {
+        (0, 0)
+    }
 }
 
-pub fn div_bar() -> String {
-    bar(light::VERTICAL_RIGHT, light::VERTICAL_LEFT, light::VERTICAL_HORIZONTAL, light::HORIZONTAL)
+/// Number of columns the board takes together with the clues left of it.
+pub fn frame_width(layout: Layout) -> i32 {
+    grid_width(layout) + clue_margin(layout).0
 }
 
-pub fn num_bar() -> String {
-    bar(light::VERTICAL, light::VERTICAL, light::VERTICAL, " ")
+/// Number of rows the board takes together with the clues above it.
+pub fn frame_height(layout: Layout) -> i32 {
+    grid_height(layout) + clue_margin(layout).1
 }
 
-pub fn bar(left: &str, right: &str, divider: &str, spacing: &str) -> String {
-    let mut bar = "".to_string();
-    for i in -12..=12 {
-        bar.push(match i {
-            -12    => left,
-            12     => right,
-            -4 | 4 => divider,
-            _      => spacing
-        }.chars().next().unwrap());
+pub fn col_number_offset(layout: Layout, c: i32) -> i32 {
+    let left = clue_margin(layout).0 - frame_width(layout)/2;
+    if layout.bordered {
+        left + c*2 + 1
+    } else {
+        left + c*2 + (c/layout.size.box_cols as i32)*2 + 2
     }
-    bar
 }
 
-pub fn col_number_offset(c: i32) -> i32 {
-    c*2 + (c/3)*2 - 10
+pub fn row_number_offset(layout: Layout, r: i32) -> i32 {
+    let top = clue_margin(layout).1 - frame_height(layout)/2;
+    if layout.bordered {
+        top + r*2 + 1
+    } else {
+        top + r + (r/layout.size.box_rows as i32) + 1
+    }
 }
 
-pub fn row_number_offset(r: i32) -> i32 {
-    r + (r/3) - 5
+/// Returns the lines of a bordered board, with a border between every two cells of different regions.
+/// `region` returns the region of a cell.
+pub fn region_bars<F: Fn(usize, usize) -> usize>(side: usize, region: F) -> Vec<String> {
+    let side = side as i32;
+    // `None` outside of the board
+    let at = |(r, c): (i32, i32)| if r < 0 || c < 0 || r >= side || c >= side { None } else { Some(region(r as usize, c as usize)) };
+    let wall = |a: (i32, i32), b: (i32, i32)| at(a) != at(b);
+    let mut bars = Vec::with_capacity(2 * side as usize + 1);
+    for y in 0..=2 * side {
+        let mut bar = String::new();
+        for x in 0..=2 * side {
+            // the cell below and right of the position
+            let (r, c) = (y / 2, x / 2);
+            bar.push_str(match (y % 2, x % 2) {
+                (1, 1) => " ",
+                (1, _) => if wall((r, c - 1), (r, c)) { light::VERTICAL } else { " " },
+                (_, 1) => if wall((r - 1, c), (r, c)) { light::HORIZONTAL } else { " " },
+                _ => junction(wall((r - 1, c - 1), (r - 1, c)), wall((r, c - 1), (r, c)),
+                              wall((r - 1, c - 1), (r, c - 1)), wall((r - 1, c), (r, c)))
+            });
+        }
+        bars.push(bar);
+    }
+    bars
+}
+
+/// Returns the lines of a board with squares, with a divider between every two squares of different grids
+/// or between a square and the outside of the board. `grid` returns the grid of a square, counted in squares,
+/// `None` for squares outside of the board like the gaps between the grids of a Samurai.
+/// Every square is a grid of its own on a single Sudoku.
+pub fn square_bars<F: Fn(usize, usize) -> Option<usize>>(layout: Layout, grid: F) -> Vec<String> {
+    let (box_rows, box_cols) = (layout.size.box_rows as i32, layout.size.box_cols as i32);
+    let (rows, cols) = (layout.side as i32 / box_rows, layout.side as i32 / box_cols);
+    let at = |(r, c): (i32, i32)| if r < 0 || c < 0 || r >= rows || c >= cols { None } else { grid(r as usize, c as usize) };
+    let wall = |a: (i32, i32), b: (i32, i32)| at(a) != at(b);
+    (0..grid_height(layout)).map(|y| (0..grid_width(layout)).map(|x| {
+        // the square below and right of the position
+        let (r, c) = (y / (box_rows + 1), x / (2 * box_cols + 2));
+        match (y % (box_rows + 1) == 0, x % (2 * box_cols + 2) == 0) {
+            (false, false) => " ",
+            (false, true)  => if wall((r, c - 1), (r, c)) { light::VERTICAL } else { " " },
+            (true, false)  => if wall((r - 1, c), (r, c)) { light::HORIZONTAL } else { " " },
+            (true, true)   => junction(wall((r - 1, c - 1), (r - 1, c)), wall((r, c - 1), (r, c)),
+                                       wall((r - 1, c - 1), (r, c - 1)), wall((r - 1, c), (r, c)))
+        }
+    }).collect()).collect()
+}
+
+/// Returns the character that joins the borders going up, down, left and right from a point.
+fn junction(up: bool, down: bool, left: bool, right: bool) -> &'static str {
+    match (up, down, left, right) {
+        (false, false, false, false) => " ",
+        // a single border can't end in the middle of the board, but on a straight line it would look the same
+        (_, _, false, false)         => light::VERTICAL,
+        (false, false, _, _)         => light::HORIZONTAL,
+        (false, true, false, true)   => light::DOWN_RIGHT,
+        (false, true, true, false)   => light::DOWN_LEFT,
+        (true, false, false, true)   => light::UP_RIGHT,
+        (true, false, true, false)   => light::UP_LEFT,
+        (true, true, false, true)    => light::VERTICAL_RIGHT,
+        (true, true, true, false)    => light::VERTICAL_LEFT,
+        (false, true, true, true)    => light::DOWN_HORIZONTAL,
+        (true, false, true, true)    => light::UP_HORIZONTAL,
+        _                            => light::VERTICAL_HORIZONTAL
+    }
 }
 
 pub fn time_top_bar() -> String {
@@ -97,14 +165,28 @@ pub fn time_bar(left: &str, right: &str, between: &str) -> String {
     bar
 }
 
-pub fn has_only_unique_elements<T>(reference_slice: &mut [&T], ignore: &T) -> bool  where T: Ord{
-    // sort and return false if neighbors are the same
-    reference_slice.sort();
-    let length = reference_slice.len();
-    for i in 0..(length - 1) {
-        if reference_slice[i] != ignore && reference_slice.get(i) == reference_slice.get(i + 1) {
-            return false;
+/// Gives every cage one of `colors` colors, so that touching cages get different colors where possible.
+/// Returns the color index of every cage.
+pub fn cage_colors(cages: &[Constraint], side: usize, colors: usize) -> Vec<usize> {
+    let mut cage_of = vec![vec![usize::MAX; side]; side];
+    for (k, cage) in cages.iter().enumerate() {
+        for &(r, c) in cage.cells() {
+            cage_of[r][c] = k;
+        }
+    }
+    let mut assigned: Vec<usize> = Vec::with_capacity(cages.len());
+    for (k, cage) in cages.iter().enumerate() {
+        let mut used = vec![false; colors];
+        for &(r, c) in cage.cells() {
+            let neighbours = [(r.wrapping_sub(1), c), (r + 1, c), (r, c.wrapping_sub(1)), (r, c + 1)];
+            for (nr, nc) in neighbours.into_iter().filter(|&(nr, nc)| nr < side && nc < side) {
+                let other = cage_of[nr][nc];
+                if other < k {
+                    used[assigned[other]] = true;
+                }
+            }
         }
+        assigned.push(used.iter().position(|&used| !used).unwrap_or(k % colors));
     }
-    true
+    assigned
 }
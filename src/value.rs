@@ -1,9 +1,10 @@
 use rand::distributions::{Distribution, Standard};
 use std::convert::TryFrom;
-//use super::Sudoku;
+use super::error::SudokuError;
 
-/// [Sudoku] is filled with SudokuValue
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
+/// [Sudoku](super::sudoku::Sudoku) is filled with SudokuValue
+/// Values above Nine are used by boards bigger than 9x9 and are displayed as letters, `Ten` is `A`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone, Hash)]
 pub enum SudokuValue {
     Empty = 0,
     One,
@@ -14,7 +15,23 @@ pub enum SudokuValue {
     Six,
     Seven,
     Eight,
-    Nine
+    Nine,
+    Ten,
+    Eleven,
+    Twelve,
+    Thirteen,
+    Fourteen,
+    Fifteen,
+    Sixteen,
+    Seventeen,
+    Eighteen,
+    Nineteen,
+    Twenty,
+    TwentyOne,
+    TwentyTwo,
+    TwentyThree,
+    TwentyFour,
+    TwentyFive
 }
 
 impl SudokuValue {
@@ -22,8 +39,20 @@ impl SudokuValue {
         [SudokuValue::One, SudokuValue::Two, SudokuValue::Three, SudokuValue::Four, SudokuValue::Five, SudokuValue::Six, SudokuValue::Seven, SudokuValue::Eight, SudokuValue::Nine]
     }
 
-    pub fn get_number_vec() -> Vec<SudokuValue> {
-        vec![SudokuValue::One, SudokuValue::Two, SudokuValue::Three, SudokuValue::Four, SudokuValue::Five, SudokuValue::Six, SudokuValue::Seven, SudokuValue::Eight, SudokuValue::Nine]
+    /// Returns the values `1..=n` of a board with `n` rows.
+    pub fn get_number_vec(n: usize) -> Vec<SudokuValue> {
+        (1..=n as i32).map(|num| SudokuValue::try_from(num).unwrap()).collect()
+    }
+
+    /// Returns the value of a digit or letter as it's displayed, `None` for other chars.
+    /// `'0'` is [SudokuValue::Empty] and the letters are case insensitive.
+    pub fn from_char(c: char) -> Option<SudokuValue> {
+        let num = match c.to_ascii_uppercase() {
+            '0'..='9' => c.to_digit(10).unwrap() as i32,
+            x @ 'A'..='P' => x as i32 - 'A' as i32 + 10,
+            _ => return None
+        };
+        SudokuValue::try_from(num).ok()
     }
 }
 
@@ -31,7 +60,8 @@ impl std::fmt::Display for SudokuValue {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             SudokuValue::Empty => write!(f, " "),
-            num => write!(f, "{}", (*num as i32))
+            num if (*num as i32) < 10 => write!(f, "{}", (*num as i32)),
+            num => write!(f, "{}", (b'A' + (*num as u8 - 10)) as char)
         }
     }
 }
@@ -40,8 +70,8 @@ impl Iterator for SudokuValue {
     type Item = SudokuValue;
     fn next(&mut self) -> Option<SudokuValue> {
         match self {
-            SudokuValue::Empty  => None,
-            SudokuValue::Nine   => None,
+            SudokuValue::Empty       => None,
+            SudokuValue::TwentyFive  => None,
             _ => {
                 let num = *self as i32 + 1;
                 *self = SudokuValue::try_from(num).unwrap();
@@ -51,7 +81,23 @@ impl Iterator for SudokuValue {
     }
 }
 
-/// Only generates number values, not [SudokuValue::Empty].
+/// Serialized as its number, `0` for [SudokuValue::Empty].
+#[cfg(feature = "serde")]
+impl serde::Serialize for SudokuValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SudokuValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let num = u8::deserialize(deserializer)?;
+        SudokuValue::try_from(num as i32).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Only generates number values of a 9x9 board, not [SudokuValue::Empty].
 impl Distribution<SudokuValue> for Standard {
     fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> SudokuValue {
         SudokuValue::try_from(rng.gen_range(1..=9)).unwrap()
@@ -59,9 +105,9 @@ impl Distribution<SudokuValue> for Standard {
 }
 
 impl TryFrom<i32> for SudokuValue {
-    type Error = &'static str;
+    type Error = SudokuError;
 
-    /// 0 corresponds to [SudokuValue::Empty], the other numbers 1..=9 correspond to their SudokuValue.
+    /// 0 corresponds to [SudokuValue::Empty], the other numbers 1..=25 correspond to their SudokuValue.
     fn try_from(num: i32) -> Result<Self, Self::Error> {
         match num {
             0 => Ok(SudokuValue::Empty),
@@ -74,7 +120,23 @@ impl TryFrom<i32> for SudokuValue {
             7 => Ok(SudokuValue::Seven),
             8 => Ok(SudokuValue::Eight),
             9 => Ok(SudokuValue::Nine),
-            _ => Err("SudokuValue has to be in the range 0..=9")
+            10 => Ok(SudokuValue::Ten),
+            11 => Ok(SudokuValue::Eleven),
+            12 => Ok(SudokuValue::Twelve),
+            13 => Ok(SudokuValue::Thirteen),
+            14 => Ok(SudokuValue::Fourteen),
+            15 => Ok(SudokuValue::Fifteen),
+            16 => Ok(SudokuValue::Sixteen),
+            17 => Ok(SudokuValue::Seventeen),
+            18 => Ok(SudokuValue::Eighteen),
+            19 => Ok(SudokuValue::Nineteen),
+            20 => Ok(SudokuValue::Twenty),
+            21 => Ok(SudokuValue::TwentyOne),
+            22 => Ok(SudokuValue::TwentyTwo),
+            23 => Ok(SudokuValue::TwentyThree),
+            24 => Ok(SudokuValue::TwentyFour),
+            25 => Ok(SudokuValue::TwentyFive),
+            _ => Err(SudokuError::Parse(format!("SudokuValue has to be in the range 0..=25, found {}", num)))
         }
     }
 }
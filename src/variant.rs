@@ -0,0 +1,247 @@
+use std::fmt::Formatter;
+use rand::Rng;
+use super::constraint::{Constraint, Shape};
+use super::killer;
+use super::markings;
+use super::sandwich;
+use super::regions::Regions;
+use super::size::Size;
+use super::sudoku::Sudoku;
+
+/// The rules a puzzle is played with.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Variant {
+    Classic,
+    /// Cages with sums instead of hints, see [Constraint::Cage].
+    Killer,
+    /// Both main diagonals have to contain every digit as well, also known as Sudoku-X.
+    Diagonal,
+    /// Windows of the size of a square, one row and column away from the squares, have to contain every digit as well.
+    /// Also known as Hyper Sudoku.
+    Windoku,
+    /// Irregular regions instead of squares, see [Regions::random].
+    Jigsaw,
+    /// Thermometers along which the digits increase, see [Constraint::Thermo].
+    Thermo,
+    /// Arrows whose digits sum up to the digit in their circle, see [Constraint::Arrow].
+    Arrow,
+    /// Kropki dots between consecutive digits and digits where one is twice the other, see [Constraint::Kropki].
+    Kropki,
+    /// Every row and column has a clue outside of the grid, the sum of the digits between its 1 and its biggest digit,
+    /// see [Constraint::Sandwich].
+    Sandwich,
+    /// Greater-than signs between neighbours instead of hints, see [Constraint::Inequality].
+    GreaterThan,
+    /// A bar between every two neighbours with consecutive digits and none between any other, see [Constraint::Consecutive].
+    Consecutive,
+    /// Orthogonal neighbours can't have consecutive digits.
+    NonConsecutive,
+    /// Shaded cells that have to contain an even or an odd digit, see [Constraint::Parity].
+    EvenOdd,
+    /// Five overlapping classic 9x9 grids, see [Samurai](super::samurai::Samurai).
+    /// Its puzzles aren't generated by [Sudoku::new].
+    Samurai,
+}
+
+impl Variant {
+    pub fn all() -> [Variant; 14] {
+        [Variant::Classic, Variant::Killer, Variant::Diagonal, Variant::Windoku, Variant::Jigsaw,
+         Variant::Thermo, Variant::Arrow, Variant::Kropki, Variant::Sandwich, Variant::GreaterThan,
+         Variant::Consecutive, Variant::NonConsecutive, Variant::EvenOdd, Variant::Samurai]
+    }
+
+    pub fn next(self) -> Self {
+        let all = Variant::all();
+        all[(self.index() + 1) % all.len()]
+    }
+
+    fn index(self) -> usize {
+        Variant::all().iter().position(|&v| v == self).unwrap()
+    }
+
+    /// Creates the regions of the variant that fit the solved board, the squares for most variants.
+    pub fn regions<R: Rng>(self, solution: &Sudoku, rng: &mut R) -> Regions {
+        match self {
+            Variant::Jigsaw => Regions::random(solution, rng),
+            _ => Regions::squares(solution.size())
+        }
+    }
+
+    /// Returns the constraints every board of the variant has, the solution has to satisfy them.
+    pub fn rules(self, size: Size) -> Vec<Constraint> {
+        let n = size.side();
+        match self {
+            Variant::Classic | Variant::Killer | Variant::Jigsaw | Variant::Thermo | Variant::Arrow | Variant::Kropki
+            | Variant::Sandwich | Variant::GreaterThan | Variant::Consecutive | Variant::EvenOdd | Variant::Samurai => Vec::new(),
+            Variant::NonConsecutive => markings::pairs(n).into_iter().map(|cells| Constraint::Consecutive { cells, bar: false }).collect(),
+            Variant::Diagonal => vec![
+                Constraint::Region { shape: Shape::Diagonal, cells: (0..n).map(|i| (i, i)).collect() },
+                Constraint::Region { shape: Shape::Diagonal, cells: (0..n).map(|i| (i, n - 1 - i)).collect() }
+            ],
+            Variant::Windoku => {
+                // a 9x9 board has windows at the rows and columns 1-3 and 5-7, counted from 0
+                let tops = (1..n).step_by(size.box_rows + 1).filter(|top| top + size.box_rows <= n);
+                let lefts = (1..n).step_by(size.box_cols + 1).filter(|left| left + size.box_cols <= n).collect::<Vec<_>>();
+                tops.flat_map(|top| lefts.iter().map(move |&left| Constraint::Region {
+                    shape: Shape::Window,
+                    cells: (top..top + size.box_rows).flat_map(|r| (left..left + size.box_cols).map(move |c| (r, c))).collect()
+                })).collect()
+            }
+        }
+    }
+
+    /// Creates the constraints of the variant that fit the solved board, on top of the [Variant::rules].
+    pub fn clues<R: Rng>(self, solution: &Sudoku, rng: &mut R) -> Vec<Constraint> {
+        match self {
+            Variant::Classic | Variant::Diagonal | Variant::Windoku | Variant::Jigsaw | Variant::NonConsecutive
+            | Variant::Samurai => Vec::new(),
+            Variant::Killer => killer::cages(solution, rng),
+            Variant::Thermo => markings::thermometers(solution, rng),
+            Variant::Arrow  => markings::arrows(solution, rng),
+            Variant::Kropki => markings::dots(solution),
+            Variant::Sandwich => sandwich::clues(solution),
+            Variant::GreaterThan => markings::inequalities(solution),
+            Variant::Consecutive => markings::bars(solution),
+            Variant::EvenOdd => markings::parities(solution)
+        }
+    }
+
+    /// Variants whose constraints replace the hints, so every hint that can be removed is removed.
+    pub fn is_minimal(self) -> bool {
+        match self {
+            Variant::Classic | Variant::Diagonal | Variant::Windoku | Variant::Jigsaw | Variant::Samurai => false,
+            Variant::Killer => true,
+            Variant::Thermo | Variant::Arrow | Variant::Kropki | Variant::Sandwich | Variant::GreaterThan
+            | Variant::Consecutive | Variant::NonConsecutive | Variant::EvenOdd => false
+        }
+    }
+
+    /// Variants whose constraints replace hints as well, but whose puzzles can rarely be solved with logic
+    /// once every hint that can be removed is removed, see [Sudoku::reduce].
+    pub fn is_reduced(self) -> bool {
+        matches!(self, Variant::Thermo | Variant::Arrow | Variant::Kropki | Variant::Sandwich | Variant::GreaterThan
+            | Variant::Consecutive | Variant::NonConsecutive | Variant::EvenOdd)
+    }
+
+    /// Variants whose clues are placed everywhere they fit the solution, so most of them are removed again
+    /// after the hints, see [Sudoku::thin_clues].
+    pub fn thins_clues(self) -> bool {
+        matches!(self, Variant::GreaterThan | Variant::EvenOdd)
+    }
+
+    /// Returns `false` for boards the puzzles of the variant take too long to generate for,
+    /// or that are too big to draw with a border around every cell.
+    pub fn supports(self, size: Size) -> bool {
+        match self {
+            Variant::Classic | Variant::Diagonal => true,
+            Variant::Killer | Variant::Windoku | Variant::Thermo | Variant::Arrow | Variant::Kropki | Variant::Sandwich
+            | Variant::GreaterThan | Variant::Consecutive | Variant::EvenOdd => size.side() <= 9,
+            // a 4x4 board can't be filled without consecutive neighbours
+            Variant::NonConsecutive => (6..=9).contains(&size.side()),
+            Variant::Jigsaw => size.side() <= 16,
+            Variant::Samurai => size == Size::NINE
+        }
+    }
+
+    /// Letters used in the [PuzzleId](super::puzzle_id::PuzzleId), empty for [Variant::Classic].
+    pub fn code(self) -> &'static str {
+        match self {
+            Variant::Classic  => "",
+            Variant::Killer   => "K",
+            Variant::Diagonal => "X",
+            Variant::Windoku  => "W",
+            Variant::Jigsaw   => "J",
+            Variant::Thermo   => "T",
+            Variant::Arrow    => "A",
+            Variant::Kropki   => "D",
+            Variant::Sandwich => "B",
+            Variant::GreaterThan => "G",
+            Variant::Consecutive => "C",
+            Variant::NonConsecutive => "N",
+            Variant::EvenOdd  => "E",
+            Variant::Samurai  => "S"
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Variant::all().iter().copied().find(|v| v.code() == code)
+    }
+}
+
+impl std::fmt::Display for Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Variant::Classic  => "Classic",
+            Variant::Killer   => "Killer",
+            Variant::Diagonal => "Diagonal",
+            Variant::Windoku  => "Windoku",
+            Variant::Jigsaw   => "Jigsaw",
+            Variant::Thermo   => "Thermo",
+            Variant::Arrow    => "Arrow",
+            Variant::Kropki   => "Kropki",
+            Variant::Sandwich => "Sandwich",
+            Variant::GreaterThan => "Greater-than",
+            Variant::Consecutive => "Consecutive",
+            Variant::NonConsecutive => "Non-consecutive",
+            Variant::EvenOdd  => "Even-odd",
+            Variant::Samurai  => "Samurai"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::settings::Settings;
+    use crate::solver::Uniqueness;
+    use crate::value::SudokuValue;
+
+    /// Returns a unique puzzle of the variant and its solution.
+    fn generate(variant: Variant, size: Size) -> (Sudoku, Sudoku) {
+        let settings = Settings { variant, size, difficulty: Difficulty::Easy, ..Settings::default() };
+        let (puzzle, solution) = Sudoku::new(&settings, 9).unwrap();
+        assert_eq!(puzzle.uniqueness(), Uniqueness::Unique, "{}", variant);
+        assert_eq!(puzzle.solution().as_ref(), Some(&solution), "{}", variant);
+        (puzzle, solution)
+    }
+
+    /// Asserts that every extra region of the solution has every digit, and that copying the digit of `a` to `b` conflicts.
+    fn assert_unique_regions(solution: &Sudoku, shape: Shape, a: (usize, usize), b: (usize, usize)) {
+        let regions = solution.constraints().iter()
+            .filter_map(|constraint| match constraint {
+                Constraint::Region { shape: s, cells } if *s == shape => Some(cells),
+                _ => None
+            })
+            .collect_vec();
+        assert!(!regions.is_empty());
+        for cells in regions {
+            let digits = cells.iter().map(|&(r, c)| *solution.get(r, c).unwrap()).collect_vec();
+            assert!(digits.iter().all_unique() && !digits.contains(&SudokuValue::Empty), "{}", shape);
+        }
+        assert!(solution.solved());
+        let mut broken = solution.clone();
+        broken.set(b.0, b.1, *solution.get(a.0, a.1).unwrap()).unwrap();
+        assert!(!broken.solved());
+        assert!(broken.conflicts().contains(&(a, b)), "{}", shape);
+    }
+
+    #[test]
+    fn diagonals_have_every_digit() {
+        for size in [Size::SIX, Size::NINE] {
+            let n = size.side();
+            let (_, solution) = generate(Variant::Diagonal, size);
+            // the two ends of the main diagonal share no row, column or square
+            assert_unique_regions(&solution, Shape::Diagonal, (0, 0), (n - 1, n - 1));
+        }
+    }
+
+    #[test]
+    fn windows_have_every_digit() {
+        let (_, solution) = generate(Variant::Windoku, Size::NINE);
+        assert_eq!(solution.constraints().len(), 4);
+        // the corners of the first window lie in different rows, columns and squares
+        assert_unique_regions(&solution, Shape::Window, (1, 1), (3, 3));
+    }
+}